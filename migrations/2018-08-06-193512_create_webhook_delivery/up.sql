@@ -0,0 +1,9 @@
+CREATE TABLE webhook_delivery (
+    delivery_id VARCHAR PRIMARY KEY,
+    event_name VARCHAR NOT NULL,
+    body VARCHAR NOT NULL,
+    received_at TIMESTAMP NOT NULL,
+    processed_at TIMESTAMP,
+    successful BOOLEAN,
+    message VARCHAR
+);
@@ -0,0 +1,10 @@
+CREATE TABLE fcp_hold (
+    id SERIAL PRIMARY KEY,
+    fk_proposal INTEGER NOT NULL REFERENCES fcp_proposal (id) ON DELETE CASCADE,
+    fk_initiator INTEGER NOT NULL REFERENCES githubuser (id),
+    fk_initiating_comment INTEGER NOT NULL REFERENCES issuecomment (id),
+    reason VARCHAR NOT NULL,
+    held_at TIMESTAMP NOT NULL,
+    fk_released_comment INTEGER REFERENCES issuecomment (id),
+    released_at TIMESTAMP
+);
@@ -0,0 +1 @@
+ALTER TABLE fcp_proposal DROP COLUMN fcp_teams;
@@ -0,0 +1,3 @@
+-- the labels of the teams asked to review the proposal, comma separated;
+-- empty for proposals made before this, which involve all labelled teams
+ALTER TABLE fcp_proposal ADD COLUMN fcp_teams VARCHAR NOT NULL DEFAULT '';
@@ -0,0 +1,9 @@
+CREATE TABLE fcp_disposition_change (
+    id SERIAL PRIMARY KEY,
+    fk_proposal INTEGER NOT NULL REFERENCES fcp_proposal (id) ON DELETE CASCADE,
+    fk_initiator INTEGER NOT NULL REFERENCES githubuser (id),
+    fk_initiating_comment INTEGER NOT NULL REFERENCES issuecomment (id),
+    old_disposition VARCHAR NOT NULL,
+    new_disposition VARCHAR NOT NULL,
+    changed_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,12 @@
+DELETE FROM fcp_proposal WHERE status IN ('cancelled', 'superseded');
+DELETE FROM fcp_proposal a USING fcp_proposal b
+WHERE a.fk_issue = b.fk_issue AND a.id < b.id;
+
+DROP INDEX fcp_proposal_active_fk_issue_key;
+ALTER TABLE fcp_proposal ADD CONSTRAINT fcp_proposal_fk_issue_key UNIQUE (fk_issue);
+
+ALTER TABLE fcp_proposal DROP COLUMN superseded_at;
+ALTER TABLE fcp_proposal DROP COLUMN cancelled_at;
+ALTER TABLE fcp_proposal DROP COLUMN finished_at;
+ALTER TABLE fcp_proposal DROP COLUMN proposed_at;
+ALTER TABLE fcp_proposal DROP COLUMN status;
@@ -0,0 +1,19 @@
+-- proposals are kept once they're cancelled or finished, so that an issue
+-- can have several of them over time, but only one active at once
+ALTER TABLE fcp_proposal ADD COLUMN status VARCHAR NOT NULL DEFAULT 'proposed';
+ALTER TABLE fcp_proposal ADD COLUMN proposed_at TIMESTAMP;
+ALTER TABLE fcp_proposal ADD COLUMN finished_at TIMESTAMP;
+ALTER TABLE fcp_proposal ADD COLUMN cancelled_at TIMESTAMP;
+ALTER TABLE fcp_proposal ADD COLUMN superseded_at TIMESTAMP;
+
+UPDATE fcp_proposal SET status = 'in-fcp' WHERE fcp_start IS NOT NULL;
+UPDATE fcp_proposal SET status = 'finished' WHERE fcp_closed;
+
+UPDATE fcp_proposal SET proposed_at = issuecomment.created_at
+FROM issuecomment
+WHERE issuecomment.id = fcp_proposal.fk_initiating_comment;
+ALTER TABLE fcp_proposal ALTER COLUMN proposed_at SET NOT NULL;
+
+ALTER TABLE fcp_proposal DROP CONSTRAINT fcp_proposal_fk_issue_key;
+CREATE UNIQUE INDEX fcp_proposal_active_fk_issue_key ON fcp_proposal (fk_issue)
+    WHERE status IN ('proposed', 'in-fcp');
@@ -0,0 +1 @@
+DROP TABLE rfcbot_event;
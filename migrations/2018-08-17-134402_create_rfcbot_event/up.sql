@@ -0,0 +1,14 @@
+-- append-only log of what the bot did, and on whose behalf
+CREATE TABLE rfcbot_event (
+    id SERIAL PRIMARY KEY,
+    fk_issue INTEGER NOT NULL REFERENCES issue (id),
+    fk_actor INTEGER REFERENCES githubuser (id),
+    fk_proposal INTEGER REFERENCES fcp_proposal (id) ON DELETE SET NULL,
+    fk_poll INTEGER REFERENCES poll (id) ON DELETE SET NULL,
+    fk_comment INTEGER REFERENCES issuecomment (id) ON DELETE SET NULL,
+    kind VARCHAR NOT NULL,
+    detail VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX rfcbot_event_fk_issue ON rfcbot_event (fk_issue);
@@ -0,0 +1,19 @@
+-- changes the bot makes on GitHub, written along with the state change they
+-- belong to and delivered (and retried) from here
+CREATE TABLE github_outbox (
+    id SERIAL PRIMARY KEY,
+    fk_issue INTEGER NOT NULL REFERENCES issue (id),
+    action VARCHAR NOT NULL,
+    comment_id INTEGER,
+    body VARCHAR NOT NULL,
+    label VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL,
+    last_error VARCHAR,
+    delivered_at TIMESTAMP,
+    gave_up_at TIMESTAMP
+);
+
+CREATE INDEX github_outbox_pending ON github_outbox (fk_issue, id)
+    WHERE delivered_at IS NULL AND gave_up_at IS NULL;
@@ -0,0 +1,8 @@
+-- validators of the last response for each listing the scraper fetches, so
+-- that it can ask GitHub whether anything changed instead of refetching it
+CREATE TABLE github_etag (
+    url VARCHAR PRIMARY KEY,
+    etag VARCHAR,
+    last_modified VARCHAR,
+    fetched_at TIMESTAMP NOT NULL
+);
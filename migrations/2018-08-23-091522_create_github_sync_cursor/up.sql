@@ -0,0 +1,12 @@
+-- where the scraper left off in each repository, so that one repository
+-- failing to sync doesn't hold up (or get mixed up with) the others
+CREATE TABLE github_sync_cursor (
+    repository VARCHAR PRIMARY KEY,
+    -- when the last successful sync started, the next one fetches changes since then
+    synced_until TIMESTAMP,
+    last_attempt_at TIMESTAMP NOT NULL,
+    successful BOOLEAN NOT NULL,
+    message VARCHAR,
+    -- consecutive failed syncs
+    failures INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,4 @@
+-- whether the proposal was made to the teams named in the command, which
+-- stay the same, rather than to those labelled on the issue, which follow
+-- the labels until the FCP starts
+ALTER TABLE fcp_proposal ADD COLUMN fcp_teams_scoped BOOLEAN NOT NULL DEFAULT false;
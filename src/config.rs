@@ -4,7 +4,6 @@ use std::collections::BTreeMap;
 use std::env;
 
 pub const RFC_BOT_MENTION: &'static str = "@rfcbot";
-pub const GH_ORGS: [&'static str; 3] = ["rust-lang", "rust-lang-nursery", "rust-lang-deprecated"];
 
 lazy_static! {
     pub static ref CONFIG: Config = {
@@ -28,8 +27,13 @@ pub struct Config {
     pub github_access_token: String,
     pub github_user_agent: String,
     pub github_webhook_secrets: Vec<String>,
+    pub github_webhook_reject_sha1: bool,
     pub github_interval_mins: u64,
     pub post_comments: bool,
+    pub admin_token: Option<String>,
+    pub rfcbot_config_path: String,
+    pub dashboard_finished_days: i64,
+    pub github_stale_sync_mins: i64,
 }
 
 impl Config {
@@ -46,6 +50,16 @@ const GITHUB_WEBHOOK_SECRETS: &'static str = "GITHUB_WEBHOOK_SECRETS";
 const GITHUB_UA: &'static str = "GITHUB_USER_AGENT";
 const GITHUB_INTERVAL: &'static str = "GITHUB_SCRAPE_INTERVAL";
 const POST_COMMENTS: &'static str = "POST_COMMENTS";
+// optional, SHA-1-only webhook deliveries are accepted unless this is "true"
+const GITHUB_WEBHOOK_REJECT_SHA1: &'static str = "GITHUB_WEBHOOK_REJECT_SHA1";
+// optional, the admin endpoints are disabled without it
+const ADMIN_TOKEN: &'static str = "ADMIN_TOKEN";
+// optional, defaults to the rfcbot.toml in the working directory
+const RFCBOT_CONFIG_PATH: &'static str = "RFCBOT_CONFIG_PATH";
+// optional, how long finished FCPs are listed on the dashboard, defaults to a week
+const DASHBOARD_FINISHED_DAYS: &'static str = "DASHBOARD_FINISHED_DAYS";
+// optional, when a repository which wasn't synced counts as stale, defaults to three scrapes
+const GITHUB_STALE_SYNC_MINS: &'static str = "GITHUB_STALE_SYNC_MINS";
 
 // this is complex, but we'll shortly need a lot more config items
 // so checking them automagically seems like a nice solution
@@ -86,14 +100,39 @@ pub fn init() -> Result<Config, Vec<&'static str>> {
         let webhook_secrets = vars.remove(GITHUB_WEBHOOK_SECRETS).unwrap();
         let webhook_secrets = webhook_secrets.split(',').map(String::from).collect();
 
+        let reject_sha1 = env::var(GITHUB_WEBHOOK_REJECT_SHA1)
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>();
+        let reject_sha1 = ok_or!(reject_sha1, throw!(vec![GITHUB_WEBHOOK_REJECT_SHA1]));
+
+        let admin_token = env::var(ADMIN_TOKEN).ok().filter(|t| !t.is_empty());
+
+        let rfcbot_config_path = env::var(RFCBOT_CONFIG_PATH)
+            .unwrap_or_else(|_| "rfcbot.toml".to_string());
+
+        let finished_days = env::var(DASHBOARD_FINISHED_DAYS)
+            .unwrap_or_else(|_| "7".to_string())
+            .parse::<i64>();
+        let finished_days = ok_or!(finished_days, throw!(vec![DASHBOARD_FINISHED_DAYS]));
+
+        let stale_sync_mins = env::var(GITHUB_STALE_SYNC_MINS)
+            .unwrap_or_else(|_| (3 * gh_interval).to_string())
+            .parse::<i64>();
+        let stale_sync_mins = ok_or!(stale_sync_mins, throw!(vec![GITHUB_STALE_SYNC_MINS]));
+
         Ok(Config {
                db_url: db_url,
                db_pool_size: db_pool_size,
                github_access_token: gh_token,
                github_user_agent: gh_ua,
                github_webhook_secrets: webhook_secrets,
+               github_webhook_reject_sha1: reject_sha1,
                github_interval_mins: gh_interval,
                post_comments: post_comments,
+               admin_token: admin_token,
+               rfcbot_config_path: rfcbot_config_path,
+               dashboard_finished_days: finished_days,
+               github_stale_sync_mins: stale_sync_mins,
            })
 
     } else {
@@ -21,6 +21,18 @@ pub struct GitHubSyncPartial {
     pub message: Option<String>,
 }
 
+#[derive(AsChangeset, Clone, Debug, Insertable, Queryable, Serialize)]
+#[table_name="github_sync_cursor"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct GitHubSyncCursor {
+    pub repository: String,
+    pub synced_until: Option<NaiveDateTime>,
+    pub last_attempt_at: NaiveDateTime,
+    pub successful: bool,
+    pub message: Option<String>,
+    pub failures: i32,
+}
+
 #[derive(AsChangeset, Clone, Debug, Deserialize, Eq, Insertable,
          Ord, PartialEq, PartialOrd, Queryable, Serialize)]
 #[table_name="githubuser"]
@@ -149,3 +161,65 @@ pub struct PullRequest {
     pub changed_files: i32,
     pub repository: String,
 }
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name="webhook_delivery"]
+pub struct NewWebhookDelivery<'a> {
+    pub delivery_id: &'a str,
+    pub event_name: &'a str,
+    pub body: &'a str,
+    pub received_at: NaiveDateTime,
+}
+
+#[derive(AsChangeset, Clone, Debug, Queryable)]
+#[table_name="webhook_delivery"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub event_name: String,
+    pub body: String,
+    pub received_at: NaiveDateTime,
+    pub processed_at: Option<NaiveDateTime>,
+    pub successful: Option<bool>,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name="github_outbox"]
+pub struct NewOutboxEntry<'a> {
+    pub fk_issue: i32,
+    pub action: &'a str,
+    pub comment_id: Option<i32>,
+    pub body: &'a str,
+    pub label: &'a str,
+    pub created_at: NaiveDateTime,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+#[derive(AsChangeset, Clone, Debug, Queryable)]
+#[table_name="github_outbox"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct OutboxEntry {
+    pub id: i32,
+    pub fk_issue: i32,
+    pub action: String,
+    pub comment_id: Option<i32>,
+    pub body: String,
+    pub label: String,
+    pub created_at: NaiveDateTime,
+    pub attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub gave_up_at: Option<NaiveDateTime>,
+}
+
+#[derive(AsChangeset, Clone, Debug, Insertable, Queryable)]
+#[table_name="github_etag"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct GitHubEtag {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched_at: NaiveDateTime,
+}
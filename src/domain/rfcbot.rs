@@ -1,4 +1,6 @@
-use chrono::NaiveDateTime;
+use std::collections::BTreeSet;
+
+use chrono::{NaiveDateTime, Utc};
 
 use super::schema::*;
 
@@ -40,6 +42,10 @@ pub struct NewFcpProposal<'a> {
     pub fk_bot_tracking_comment: i32,
     pub fcp_start: Option<NaiveDateTime>,
     pub fcp_closed: bool,
+    pub fcp_teams: &'a str,
+    pub status: &'a str,
+    pub proposed_at: NaiveDateTime,
+    pub fcp_teams_scoped: bool,
 }
 
 #[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd, Serialize)]
@@ -73,6 +79,64 @@ pub struct FcpProposal {
     pub fk_bot_tracking_comment: i32,
     pub fcp_start: Option<NaiveDateTime>,
     pub fcp_closed: bool,
+    pub fcp_teams: String,
+    pub status: String,
+    pub proposed_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub superseded_at: Option<NaiveDateTime>,
+    pub fcp_teams_scoped: bool,
+}
+
+impl FcpProposal {
+    /// The labels of the teams the proposal was made to, given the teams
+    /// labelled on its issue. Proposals which weren't scoped to teams are
+    /// for the labelled teams, which are stored with them as well, except
+    /// for those made before the teams were stored.
+    pub fn teams(&self, labelled: &BTreeSet<String>) -> BTreeSet<String> {
+        if !self.fcp_teams_scoped && self.fcp_teams.is_empty() {
+            return labelled.clone();
+        }
+
+        self.fcp_teams
+            .split(',')
+            .filter(|team| !team.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Is this the proposal its issue is going through at the moment?
+    pub fn is_active(&self) -> bool {
+        self.status == ProposalStatus::Proposed.repr() ||
+        self.status == ProposalStatus::InFcp.repr()
+    }
+}
+
+/// Where a proposal is in its lifecycle, as stored in `fcp_proposal.status`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProposalStatus {
+    /// Waiting for reviews, or for concerns to be resolved.
+    Proposed,
+    /// In its final comment period, since `fcp_start`.
+    InFcp,
+    /// The final comment period is complete.
+    Finished,
+    /// Cancelled before its final comment period was complete.
+    Cancelled,
+    /// Finished, but followed by a newer proposal for the same issue.
+    Superseded,
+}
+
+impl ProposalStatus {
+    pub fn repr(self) -> &'static str {
+        match self {
+            ProposalStatus::Proposed => "proposed",
+            ProposalStatus::InFcp => "in-fcp",
+            ProposalStatus::Finished => "finished",
+            ProposalStatus::Cancelled => "cancelled",
+            ProposalStatus::Superseded => "superseded",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd, Serialize)]
@@ -114,6 +178,54 @@ pub struct FcpConcern {
     pub fk_initiating_comment: i32,
 }
 
+#[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd)]
+#[table_name="fcp_disposition_change"]
+pub struct NewFcpDispositionChange<'a> {
+    pub fk_proposal: i32,
+    pub fk_initiator: i32,
+    pub fk_initiating_comment: i32,
+    pub old_disposition: &'a str,
+    pub new_disposition: &'a str,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Queryable)]
+pub struct FcpDispositionChange {
+    pub id: i32,
+    pub fk_proposal: i32,
+    pub fk_initiator: i32,
+    pub fk_initiating_comment: i32,
+    pub old_disposition: String,
+    pub new_disposition: String,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd)]
+#[table_name="fcp_hold"]
+pub struct NewFcpHold<'a> {
+    pub fk_proposal: i32,
+    pub fk_initiator: i32,
+    pub fk_initiating_comment: i32,
+    pub reason: &'a str,
+    pub held_at: NaiveDateTime,
+    pub fk_released_comment: Option<i32>,
+    pub released_at: Option<NaiveDateTime>,
+}
+
+#[derive(AsChangeset, Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Queryable)]
+#[table_name="fcp_hold"]
+#[changeset_options(treat_none_as_null="true")]
+pub struct FcpHold {
+    pub id: i32,
+    pub fk_proposal: i32,
+    pub fk_initiator: i32,
+    pub fk_initiating_comment: i32,
+    pub reason: String,
+    pub held_at: NaiveDateTime,
+    pub fk_released_comment: Option<i32>,
+    pub released_at: Option<NaiveDateTime>,
+}
+
 #[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd)]
 #[table_name="rfc_feedback_request"]
 pub struct NewFeedbackRequest {
@@ -132,3 +244,99 @@ pub struct FeedbackRequest {
     pub fk_issue: i32,
     pub fk_feedback_comment: Option<i32>,
 }
+
+/// Something the bot did, as recorded in the `rfcbot_event` audit log.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    FcpProposed,
+    FcpCancelled,
+    FcpStarted,
+    FcpFinished,
+    BackToProposed,
+    DispositionChanged,
+    Reviewed,
+    ConcernRaised,
+    ConcernResolved,
+    ConcernReopened,
+    ConcernDropped,
+    ConcernTransferred,
+    HoldPlaced,
+    HoldReleased,
+    PollStarted,
+    FeedbackRequested,
+    CommandFeedback,
+    LabelAdded,
+    LabelRemoved,
+    IssueClosed,
+}
+
+impl EventKind {
+    pub fn repr(self) -> &'static str {
+        use self::EventKind::*;
+        match self {
+            FcpProposed => "fcp-proposed",
+            FcpCancelled => "fcp-cancelled",
+            FcpStarted => "fcp-started",
+            FcpFinished => "fcp-finished",
+            BackToProposed => "back-to-proposed",
+            DispositionChanged => "disposition-changed",
+            Reviewed => "reviewed",
+            ConcernRaised => "concern-raised",
+            ConcernResolved => "concern-resolved",
+            ConcernReopened => "concern-reopened",
+            ConcernDropped => "concern-dropped",
+            ConcernTransferred => "concern-transferred",
+            HoldPlaced => "hold-placed",
+            HoldReleased => "hold-released",
+            PollStarted => "poll-started",
+            FeedbackRequested => "feedback-requested",
+            CommandFeedback => "command-feedback",
+            LabelAdded => "label-added",
+            LabelRemoved => "label-removed",
+            IssueClosed => "issue-closed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Insertable, Ord, PartialEq, PartialOrd)]
+#[table_name="rfcbot_event"]
+pub struct NewRfcbotEvent<'a> {
+    pub fk_issue: i32,
+    pub fk_actor: Option<i32>,
+    pub fk_proposal: Option<i32>,
+    pub fk_poll: Option<i32>,
+    pub fk_comment: Option<i32>,
+    pub kind: &'a str,
+    pub detail: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewRfcbotEvent<'a> {
+    /// An event of the given kind on an issue, happening now, with everything
+    /// else left empty.
+    pub fn new(fk_issue: i32, kind: EventKind) -> Self {
+        NewRfcbotEvent {
+            fk_issue,
+            fk_actor: None,
+            fk_proposal: None,
+            fk_poll: None,
+            fk_comment: None,
+            kind: kind.repr(),
+            detail: "",
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Queryable, Serialize)]
+pub struct RfcbotEvent {
+    pub id: i32,
+    pub fk_issue: i32,
+    pub fk_actor: Option<i32>,
+    pub fk_proposal: Option<i32>,
+    pub fk_poll: Option<i32>,
+    pub fk_comment: Option<i32>,
+    pub kind: String,
+    pub detail: String,
+    pub created_at: NaiveDateTime,
+}
@@ -9,6 +9,31 @@ table! {
     }
 }
 
+table! {
+    fcp_disposition_change (id) {
+        id -> Int4,
+        fk_proposal -> Int4,
+        fk_initiator -> Int4,
+        fk_initiating_comment -> Int4,
+        old_disposition -> Varchar,
+        new_disposition -> Varchar,
+        changed_at -> Timestamp,
+    }
+}
+
+table! {
+    fcp_hold (id) {
+        id -> Int4,
+        fk_proposal -> Int4,
+        fk_initiator -> Int4,
+        fk_initiating_comment -> Int4,
+        reason -> Varchar,
+        held_at -> Timestamp,
+        fk_released_comment -> Nullable<Int4>,
+        released_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     fcp_proposal (id) {
         id -> Int4,
@@ -19,6 +44,13 @@ table! {
         fk_bot_tracking_comment -> Int4,
         fcp_start -> Nullable<Timestamp>,
         fcp_closed -> Bool,
+        fcp_teams -> Varchar,
+        status -> Varchar,
+        proposed_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        cancelled_at -> Nullable<Timestamp>,
+        superseded_at -> Nullable<Timestamp>,
+        fcp_teams_scoped -> Bool,
     }
 }
 
@@ -31,6 +63,43 @@ table! {
     }
 }
 
+table! {
+    github_etag (url) {
+        url -> Varchar,
+        etag -> Nullable<Varchar>,
+        last_modified -> Nullable<Varchar>,
+        fetched_at -> Timestamp,
+    }
+}
+
+table! {
+    github_outbox (id) {
+        id -> Int4,
+        fk_issue -> Int4,
+        action -> Varchar,
+        comment_id -> Nullable<Int4>,
+        body -> Varchar,
+        label -> Varchar,
+        created_at -> Timestamp,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Varchar>,
+        delivered_at -> Nullable<Timestamp>,
+        gave_up_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    github_sync_cursor (repository) {
+        repository -> Varchar,
+        synced_until -> Nullable<Timestamp>,
+        last_attempt_at -> Timestamp,
+        successful -> Bool,
+        message -> Nullable<Varchar>,
+        failures -> Int4,
+    }
+}
+
 table! {
     githubsync (id) {
         id -> Int4,
@@ -119,6 +188,20 @@ table! {
     }
 }
 
+table! {
+    rfcbot_event (id) {
+        id -> Int4,
+        fk_issue -> Int4,
+        fk_actor -> Nullable<Int4>,
+        fk_proposal -> Nullable<Int4>,
+        fk_poll -> Nullable<Int4>,
+        fk_comment -> Nullable<Int4>,
+        kind -> Varchar,
+        detail -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     rfc_feedback_request (id) {
         id -> Int4,
@@ -152,12 +235,29 @@ table! {
     }
 }
 
+table! {
+    webhook_delivery (delivery_id) {
+        delivery_id -> Varchar,
+        event_name -> Varchar,
+        body -> Varchar,
+        received_at -> Timestamp,
+        processed_at -> Nullable<Timestamp>,
+        successful -> Nullable<Bool>,
+        message -> Nullable<Varchar>,
+    }
+}
+
 joinable!(fcp_concern -> githubuser (fk_initiator));
 joinable!(fcp_concern -> fcp_proposal (fk_proposal));
+joinable!(fcp_disposition_change -> githubuser (fk_initiator));
+joinable!(fcp_disposition_change -> fcp_proposal (fk_proposal));
+joinable!(fcp_hold -> githubuser (fk_initiator));
+joinable!(fcp_hold -> fcp_proposal (fk_proposal));
 joinable!(fcp_proposal -> githubuser (fk_initiator));
 joinable!(fcp_proposal -> issue (fk_issue));
 joinable!(fcp_review_request -> fcp_proposal (fk_proposal));
 joinable!(fcp_review_request -> githubuser (fk_reviewer));
+joinable!(github_outbox -> issue (fk_issue));
 joinable!(issue -> milestone (fk_milestone));
 joinable!(issuecomment -> issue (fk_issue));
 joinable!(issuecomment -> githubuser (fk_user));
@@ -166,6 +266,8 @@ joinable!(pullrequest -> githubuser (fk_assignee));
 joinable!(pullrequest -> milestone (fk_milestone));
 joinable!(rfc_feedback_request -> issuecomment (fk_feedback_comment));
 joinable!(rfc_feedback_request -> issue (fk_issue));
+joinable!(rfcbot_event -> issue (fk_issue));
+joinable!(rfcbot_event -> githubuser (fk_actor));
 joinable!(poll -> githubuser (fk_initiator));
 joinable!(poll -> issue (fk_issue));
 joinable!(poll_response_request -> poll (fk_poll));
@@ -174,10 +276,15 @@ joinable!(poll_response_request -> githubuser (fk_respondent));
 
 allow_tables_to_appear_in_same_query!(fcp_concern, githubuser);
 allow_tables_to_appear_in_same_query!(fcp_concern, fcp_proposal);
+allow_tables_to_appear_in_same_query!(fcp_disposition_change, githubuser);
+allow_tables_to_appear_in_same_query!(fcp_disposition_change, fcp_proposal);
+allow_tables_to_appear_in_same_query!(fcp_hold, githubuser);
+allow_tables_to_appear_in_same_query!(fcp_hold, fcp_proposal);
 allow_tables_to_appear_in_same_query!(fcp_proposal, githubuser);
 allow_tables_to_appear_in_same_query!(fcp_proposal, issue);
 allow_tables_to_appear_in_same_query!(fcp_review_request, fcp_proposal);
 allow_tables_to_appear_in_same_query!(fcp_review_request, githubuser);
+allow_tables_to_appear_in_same_query!(github_outbox, issue);
 allow_tables_to_appear_in_same_query!(issue, milestone);
 allow_tables_to_appear_in_same_query!(issuecomment, issue);
 allow_tables_to_appear_in_same_query!(issuecomment, githubuser);
@@ -186,6 +293,8 @@ allow_tables_to_appear_in_same_query!(pullrequest, githubuser);
 allow_tables_to_appear_in_same_query!(pullrequest, milestone);
 allow_tables_to_appear_in_same_query!(rfc_feedback_request, issuecomment);
 allow_tables_to_appear_in_same_query!(rfc_feedback_request, issue);
+allow_tables_to_appear_in_same_query!(rfcbot_event, issue);
+allow_tables_to_appear_in_same_query!(rfcbot_event, githubuser);
 allow_tables_to_appear_in_same_query!(poll, githubuser);
 allow_tables_to_appear_in_same_query!(poll, issue);
 allow_tables_to_appear_in_same_query!(poll_response_request, poll);
@@ -2,11 +2,10 @@
 
 use std::collections::BTreeMap;
 use std::io::Read;
+use std::sync::Mutex;
 use std::thread::sleep;
-use std::time::Duration;
-use std::u32;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use hyper;
 use hyper::client::{RedirectPolicy, RequestBuilder, Response};
 use hyper::header::{Headers, Authorization, UserAgent};
@@ -22,25 +21,144 @@ use github::models::{CommentFromJson, IssueFromJson, PullRequestFromJson, PullRe
 
 pub const BASE_URL: &'static str = "https://api.github.com";
 
-pub const DELAY: u64 = 300;
+/// Reads (i.e. the scraper) pause once only this many requests are left,
+/// to keep some for the changes the bot makes in the meantime.
+const RATE_LIMIT_RESERVE: u32 = 100;
+
+/// How long to wait after being rate limited without being told for how long.
+const DEFAULT_RETRY_AFTER_SECS: i64 = 60;
+
+/// How often a read is tried while being rate limited. Writes aren't retried
+/// here, the outbox takes care of that.
+const MAX_RATE_LIMITED_ATTEMPTS: u32 = 3;
 
 type ParameterMap = BTreeMap<&'static str, String>;
 
 header! { (TZ, "Time-Zone") => [String] }
 header! { (Accept, "Accept") => [String] }
+header! { (RateLimitLimit, "X-RateLimit-Limit") => [u32] }
 header! { (RateLimitRemaining, "X-RateLimit-Remaining") => [u32] }
 header! { (RateLimitReset, "X-RateLimit-Reset") => [i64] }
+header! { (RetryAfter, "Retry-After") => [i64] }
+header! { (ETag, "ETag") => [String] }
+header! { (LastModified, "Last-Modified") => [String] }
+header! { (IfNoneMatch, "If-None-Match") => [String] }
+header! { (IfModifiedSince, "If-Modified-Since") => [String] }
 header! { (Link, "Link") => [String] }
 
 const PER_PAGE: u32 = 100;
 
+/// The parts of the GitHub API that rfcbot reads from and writes to.
+///
+/// Everything that talks to GitHub (the scraper and the FCP state machine in
+/// `nag`) goes through this trait, so that the in-memory fake can stand in
+/// for the real `Client` in tests.
+pub trait GitHubApi: Sync {
+    /// List the full names (`org/repo`) of all repositories in `org`.
+    fn org_repos(&self, org: &str) -> DashResult<Vec<String>>;
+
+    /// All issues and PRs in `repo` updated since `start`, unless they're the
+    /// same as in the response `cached` was taken from.
+    fn issues_since(&self,
+                    repo: &str,
+                    start: DateTime<Utc>,
+                    cached: Option<&Validators>)
+                    -> DashResult<Fetched<Vec<IssueFromJson>>>;
+
+    /// All issue comments in `repo` updated since `start`, unless they're the
+    /// same as in the response `cached` was taken from.
+    fn comments_since(&self,
+                      repo: &str,
+                      start: DateTime<Utc>,
+                      cached: Option<&Validators>)
+                      -> DashResult<Fetched<Vec<CommentFromJson>>>;
+
+    fn fetch_pull_request(&self, pr_info: &PullRequestUrls) -> DashResult<PullRequestFromJson>;
+
+    fn close_issue(&self, repo: &str, issue_num: i32) -> DashResult<()>;
+
+    fn add_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()>;
+
+    fn remove_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()>;
+
+    fn new_comment(&self, repo: &str, issue_num: i32, text: &str) -> DashResult<CommentFromJson>;
+
+    fn edit_comment(&self,
+                    repo: &str,
+                    comment_num: i32,
+                    text: &str)
+                    -> DashResult<CommentFromJson>;
+
+    /// The API budget as of the last response, if known.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+}
+
+/// What identifies a response we've seen before, for conditional requests.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_headers(h: &Headers) -> Option<Self> {
+        let validators = Validators {
+            etag: h.get::<ETag>().map(|etag| etag.0.clone()),
+            last_modified: h.get::<LastModified>().map(|modified| modified.0.clone()),
+        };
+
+        if validators.etag.is_some() || validators.last_modified.is_some() {
+            Some(validators)
+        } else {
+            None
+        }
+    }
+}
+
+/// The outcome of a conditional request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fetched<T> {
+    /// The response is the same as the one the validators were taken from.
+    NotModified,
+    /// The validators to make the same request with next time, if the response
+    /// can be checked for changes as a whole, i.e. it fit into a single page.
+    Modified(T, Option<Validators>),
+}
+
+/// GitHub's hourly budget of API requests, as reported with every response.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    fn from_headers(h: &Headers) -> Option<Self> {
+        let remaining = h.get::<RateLimitRemaining>()?;
+        let reset = h.get::<RateLimitReset>()?;
+        Some(RateLimit {
+            limit: h.get::<RateLimitLimit>().map_or(0, |limit| **limit),
+            remaining: **remaining,
+            reset: Utc.timestamp(**reset, 0),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RequestKind {
+    Read,
+    Write,
+}
+
 #[derive(Debug)]
 pub struct Client {
     token: String,
     ua: String,
     client: hyper::Client,
-    rate_limit: u32,
-    rate_limit_timeout: DateTime<Utc>,
+    rate_limit: Mutex<Option<RateLimit>>,
 }
 
 fn read_to_string<R: Read>(reader: &mut R) -> DashResult<String> {    
@@ -59,12 +177,13 @@ impl Client {
             token: CONFIG.github_access_token.clone(),
             ua: CONFIG.github_user_agent.clone(),
             client: client,
-            rate_limit: u32::MAX,
-            rate_limit_timeout: Utc::now(),
+            rate_limit: Mutex::new(None),
         }
     }
+}
 
-    pub fn org_repos(&self, org: &str) -> DashResult<Vec<String>> {
+impl GitHubApi for Client {
+    fn org_repos(&self, org: &str) -> DashResult<Vec<String>> {
         let url = format!("{}/orgs/{}/repos", BASE_URL, org);
         let vals: Vec<serde_json::Value> = self.get_models(&url, None)?;
 
@@ -84,45 +203,37 @@ impl Client {
         Ok(repos)
     }
 
-    pub fn issues_since(&self, repo: &str, start: DateTime<Utc>) -> DashResult<Vec<IssueFromJson>> {
-        self.get_models(&format!("{}/repos/{}/issues", BASE_URL, repo),
+    fn issues_since(&self,
+                    repo: &str,
+                    start: DateTime<Utc>,
+                    cached: Option<&Validators>)
+                    -> DashResult<Fetched<Vec<IssueFromJson>>> {
+        self.get_models_if_modified(&format!("{}/repos/{}/issues", BASE_URL, repo),
             Some(&btreemap! {
                 "state" => "all".to_string(),
                 "since" => format!("{:?}", start),
                 "per_page" => format!("{}", PER_PAGE),
                 "direction" => "asc".to_string()    
-            }))
+            }),
+            cached)
     }
 
-    pub fn comments_since(&self,
+    fn comments_since(&self,
                           repo: &str,
-                          start: DateTime<Utc>)
-                          -> DashResult<Vec<CommentFromJson>> {
-        self.get_models(&format!("{}/repos/{}/issues/comments", BASE_URL, repo),
+                          start: DateTime<Utc>,
+                          cached: Option<&Validators>)
+                          -> DashResult<Fetched<Vec<CommentFromJson>>> {
+        self.get_models_if_modified(&format!("{}/repos/{}/issues/comments", BASE_URL, repo),
             Some(&btreemap! {
                 "sort" => "created".to_string(),
                 "direction" => "asc".to_string(),
                 "since" => format!("{:?}", start),
                 "per_page" => format!("{}", PER_PAGE)
-            }))
+            }),
+            cached)
     }
 
-    fn get_models<M: DeserializeOwned>(&self,
-                                       start_url: &str,
-                                       params: Option<&ParameterMap>)
-                                       -> DashResult<Vec<M>> {
-
-        let mut res = self.get(start_url, params)?;
-        let mut models = self.deserialize::<Vec<M>>(&mut res)?;
-        while let Some(url) = Self::next_page(&res.headers) {
-            sleep(Duration::from_millis(DELAY));
-            res = self.get(&url, None)?;
-            models.extend(self.deserialize::<Vec<M>>(&mut res)?);
-        }
-        Ok(models)
-    }
-
-    pub fn fetch_pull_request(&self, pr_info: &PullRequestUrls) -> DashResult<PullRequestFromJson> {
+    fn fetch_pull_request(&self, pr_info: &PullRequestUrls) -> DashResult<PullRequestFromJson> {
         if let Some(url) = pr_info.get("url") {
             let mut res = self.get(url, None)?;
             self.deserialize(&mut res)
@@ -131,30 +242,7 @@ impl Client {
         }
     }
 
-    fn next_page(h: &Headers) -> Option<String> {
-        if let Some(lh) = h.get::<Link>() {
-            for link in (**lh).split(',').map(|s| s.trim()) {
-
-                let tokens = link.split(';').map(|s| s.trim()).collect::<Vec<_>>();
-
-                if tokens.len() != 2 {
-                    continue;
-                }
-
-                if tokens[1] == "rel=\"next\"" {
-                    let url = tokens[0]
-                        .trim_left_matches('<')
-                        .trim_right_matches('>')
-                        .to_string();
-                    return Some(url);
-                }
-            }
-        }
-
-        None
-    }
-
-    pub fn close_issue(&self, repo: &str, issue_num: i32) -> DashResult<()> {
+    fn close_issue(&self, repo: &str, issue_num: i32) -> DashResult<()> {
         let url = format!("{}/repos/{}/issues/{}", BASE_URL, repo, issue_num);
         let payload = serde_json::to_string(&btreemap!("state" => "closed"))?;
         let mut res = self.patch(&url, &payload)?;
@@ -166,7 +254,7 @@ impl Client {
         Ok(())
     }
 
-    pub fn add_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
+    fn add_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
         let url = format!("{}/repos/{}/issues/{}/labels", BASE_URL, repo, issue_num);
         let payload = serde_json::to_string(&[label])?;
 
@@ -179,7 +267,7 @@ impl Client {
         Ok(())
     }
 
-    pub fn remove_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
+    fn remove_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
         let url = format!("{}/repos/{}/issues/{}/labels/{}",
                           BASE_URL,
                           repo,
@@ -187,14 +275,15 @@ impl Client {
                           label);
         let mut res = self.delete(&url)?;
 
-        if StatusCode::NoContent != res.status {
+        // a 404 means the label isn't there, which is what we wanted
+        if StatusCode::NoContent != res.status && StatusCode::NotFound != res.status {
             throw!(DashError::Misc(Some(read_to_string(&mut res)?)))
         }
 
         Ok(())
     }
 
-    pub fn new_comment(&self,
+    fn new_comment(&self,
                        repo: &str,
                        issue_num: i32,
                        text: &str)
@@ -205,7 +294,7 @@ impl Client {
         self.deserialize(&mut self.post(&url, &payload)?)
     }
 
-    pub fn edit_comment(&self,
+    fn edit_comment(&self,
                         repo: &str,
                         comment_num: i32,
                         text: &str)
@@ -221,23 +310,92 @@ impl Client {
         self.deserialize(&mut self.patch(&url, &payload)?)
     }
 
-    fn patch(&self, url: &str, payload: &str) -> Result<Response, hyper::error::Error> {
-        self.set_headers(self.client.patch(url).body(payload))
-            .send()
+    fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+}
+
+impl Client {
+    fn get_models<M: DeserializeOwned>(&self,
+                                       start_url: &str,
+                                       params: Option<&ParameterMap>)
+                                       -> DashResult<Vec<M>> {
+        match self.get_models_if_modified(start_url, params, None)? {
+            Fetched::Modified(models, _) => Ok(models),
+            Fetched::NotModified => unreachable!("unconditional request wasn't modified"),
+        }
+    }
+
+    /// Like `get_models`, but only if the first page changed since `cached`
+    /// was taken. GitHub doesn't count requests answered with a 304 against
+    /// the rate limit.
+    fn get_models_if_modified<M: DeserializeOwned>(&self,
+                                                   start_url: &str,
+                                                   params: Option<&ParameterMap>,
+                                                   cached: Option<&Validators>)
+                                                   -> DashResult<Fetched<Vec<M>>> {
+
+        let mut res = self.get_if_modified(start_url, params, cached)?;
+        if res.status == StatusCode::NotModified {
+            debug!("{} wasn't modified", start_url);
+            return Ok(Fetched::NotModified);
+        }
+
+        let mut validators = Validators::from_headers(&res.headers);
+        let mut models = self.deserialize::<Vec<M>>(&mut res)?;
+        while let Some(url) = Self::next_page(&res.headers) {
+            // later pages can change while the first one stays the same
+            validators = None;
+            res = self.get(&url, None)?;
+            models.extend(self.deserialize::<Vec<M>>(&mut res)?);
+        }
+        Ok(Fetched::Modified(models, validators))
+    }
+
+    fn next_page(h: &Headers) -> Option<String> {
+        if let Some(lh) = h.get::<Link>() {
+            for link in (**lh).split(',').map(|s| s.trim()) {
+
+                let tokens = link.split(';').map(|s| s.trim()).collect::<Vec<_>>();
+
+                if tokens.len() != 2 {
+                    continue;
+                }
+
+                if tokens[1] == "rel=\"next\"" {
+                    let url = tokens[0]
+                        .trim_left_matches('<')
+                        .trim_right_matches('>')
+                        .to_string();
+                    return Some(url);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn patch(&self, url: &str, payload: &str) -> DashResult<Response> {
+        self.send(RequestKind::Write, || self.client.patch(url).body(payload))
+    }
+
+    fn post(&self, url: &str, payload: &str) -> DashResult<Response> {
+        self.send(RequestKind::Write, || self.client.post(url).body(payload))
     }
 
-    fn post(&self, url: &str, payload: &str) -> Result<Response, hyper::error::Error> {
-        self.set_headers(self.client.post(url).body(payload)).send()
+    fn delete(&self, url: &str) -> DashResult<Response> {
+        self.send(RequestKind::Write, || self.client.delete(url))
     }
 
-    fn delete(&self, url: &str) -> Result<Response, hyper::error::Error> {
-        self.set_headers(self.client.delete(url)).send()
+    fn get(&self, url: &str, params: Option<&ParameterMap>) -> DashResult<Response> {
+        self.get_if_modified(url, params, None)
     }
 
-    fn get(&self,
-           url: &str,
-           params: Option<&ParameterMap>)
-           -> Result<Response, hyper::error::Error> {
+    fn get_if_modified(&self,
+                       url: &str,
+                       params: Option<&ParameterMap>,
+                       cached: Option<&Validators>)
+                       -> DashResult<Response> {
         let qp_string = match params {
             Some(p) => {
                 let mut qp = String::from("?");
@@ -256,7 +414,73 @@ impl Client {
 
         debug!("GETing: {}", &url);
 
-        self.set_headers(self.client.get(&url)).send()
+        self.send(RequestKind::Read, || {
+            let mut req = self.client.get(&url);
+            if let Some(cached) = cached {
+                if let Some(ref etag) = cached.etag {
+                    req = req.header(IfNoneMatch(etag.clone()));
+                }
+                if let Some(ref modified) = cached.last_modified {
+                    req = req.header(IfModifiedSince(modified.clone()));
+                }
+            }
+            req
+        })
+    }
+
+    /// Send a request, keeping track of the rate limit. Reads wait for the
+    /// budget to be reset when it's running low, and are retried when they're
+    /// rate limited anyway. Writes fail instead, to be retried by the outbox.
+    fn send<'a, F>(&self, kind: RequestKind, request: F) -> DashResult<Response>
+        where F: Fn() -> RequestBuilder<'a>
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+
+            if let Some(limit) = self.rate_limit() {
+                if let Some(pause) = pause_before(kind, &limit, Utc::now()) {
+                    if kind == RequestKind::Write {
+                        throw!(DashError::Misc(Some(format!(
+                            "GitHub API rate limit exhausted until {}", limit.reset))));
+                    }
+                    warn!("{} of {} GitHub API requests left, pausing for {}s until {}",
+                          limit.remaining, limit.limit, pause.num_seconds(), limit.reset);
+                    sleep(pause.to_std().unwrap_or_default());
+                }
+            }
+
+            let mut res = self.set_headers(request()).send()?;
+            if let Some(limit) = RateLimit::from_headers(&res.headers) {
+                *self.rate_limit.lock().unwrap() = Some(limit);
+            }
+
+            let wait = retry_after(res.status, &res.headers, Utc::now());
+            let wait = match wait {
+                // only the body tells a secondary rate limit from other 403s,
+                // so once it's read the response can only be an error
+                None if res.status == StatusCode::Forbidden => {
+                    let body = read_to_string(&mut res)?;
+                    if !is_secondary_rate_limit(&body) {
+                        throw!(DashError::Misc(Some(body)))
+                    }
+                    Some(Duration::seconds(DEFAULT_RETRY_AFTER_SECS))
+                }
+                wait => wait,
+            };
+
+            match wait {
+                Some(wait) if kind == RequestKind::Read && attempts < MAX_RATE_LIMITED_ATTEMPTS => {
+                    warn!("Rate limited by GitHub, retrying in {}s", wait.num_seconds());
+                    sleep(wait.to_std().unwrap_or_default());
+                }
+                Some(wait) => {
+                    throw!(DashError::Misc(Some(format!(
+                        "rate limited by GitHub, try again in {}s", wait.num_seconds()))));
+                }
+                None => return Ok(res),
+            }
+        }
     }
 
     fn deserialize<M: DeserializeOwned>(&self, res: &mut Response) -> DashResult<M> {
@@ -280,3 +504,124 @@ impl Client {
             .header(hyper::header::Connection::close())
     }
 }
+
+/// How long to wait before making a request, given the rate limit.
+fn pause_before(kind: RequestKind, limit: &RateLimit, now: DateTime<Utc>) -> Option<Duration> {
+    let reserve = match kind {
+        RequestKind::Read => RATE_LIMIT_RESERVE,
+        RequestKind::Write => 0,
+    };
+
+    if limit.remaining <= reserve && limit.reset > now {
+        Some(limit.reset - now + Duration::seconds(1))
+    } else {
+        None
+    }
+}
+
+/// If a response says we've been rate limited, how long to wait before trying
+/// again. This covers running out of budget as well as the secondary rate
+/// limits, which come with a `Retry-After` header (or a 429) instead. Those
+/// which come without either can only be told apart by the body, see
+/// `is_secondary_rate_limit`.
+fn retry_after(status: StatusCode, h: &Headers, now: DateTime<Utc>) -> Option<Duration> {
+    if status != StatusCode::Forbidden && status != StatusCode::TooManyRequests {
+        return None;
+    }
+
+    if let Some(after) = h.get::<RetryAfter>() {
+        return Some(Duration::seconds(**after));
+    }
+
+    match RateLimit::from_headers(h) {
+        Some(ref limit) if limit.remaining == 0 =>
+            Some(::std::cmp::max(limit.reset - now, Duration::zero()) + Duration::seconds(1)),
+        _ if status == StatusCode::TooManyRequests =>
+            Some(Duration::seconds(DEFAULT_RETRY_AFTER_SECS)),
+        _ => None,
+    }
+}
+
+/// Whether the body of a 403 says we've hit a secondary rate limit.
+fn is_secondary_rate_limit(body: &str) -> bool {
+    body.to_lowercase().contains("secondary rate limit")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn headers(remaining: u32, reset: DateTime<Utc>) -> Headers {
+        let mut h = Headers::new();
+        h.set(RateLimitLimit(5000));
+        h.set(RateLimitRemaining(remaining));
+        h.set(RateLimitReset(reset.timestamp()));
+        h
+    }
+
+    #[test]
+    fn rate_limit_from_headers() {
+        let reset = Utc.timestamp(1534766400, 0);
+        assert_eq!(RateLimit::from_headers(&headers(4321, reset)),
+                   Some(RateLimit { limit: 5000, remaining: 4321, reset }));
+        assert_eq!(RateLimit::from_headers(&Headers::new()), None);
+    }
+
+    #[test]
+    fn reads_pause_before_the_budget_runs_out() {
+        let now = Utc::now();
+        let reset = now + Duration::minutes(10);
+        let limit = |remaining| RateLimit { limit: 5000, remaining, reset };
+
+        assert_eq!(pause_before(RequestKind::Read, &limit(1000), now), None);
+        assert_eq!(pause_before(RequestKind::Read, &limit(RATE_LIMIT_RESERVE), now),
+                   Some(Duration::minutes(10) + Duration::seconds(1)));
+        assert_eq!(pause_before(RequestKind::Write, &limit(RATE_LIMIT_RESERVE), now), None);
+        assert!(pause_before(RequestKind::Write, &limit(0), now).is_some());
+
+        // once the budget has been reset there's no need to wait
+        assert_eq!(pause_before(RequestKind::Read, &limit(0), reset), None);
+    }
+
+    #[test]
+    fn rate_limited_responses() {
+        let now = Utc::now();
+        let reset = now + Duration::seconds(30);
+
+        let mut secondary = headers(4000, reset);
+        secondary.set(RetryAfter(120));
+        assert_eq!(retry_after(StatusCode::Forbidden, &secondary, now),
+                   Some(Duration::seconds(120)));
+
+        assert_eq!(retry_after(StatusCode::Forbidden, &headers(0, reset), now),
+                   Some(Duration::seconds(31)));
+        assert_eq!(retry_after(StatusCode::TooManyRequests, &Headers::new(), now),
+                   Some(Duration::seconds(DEFAULT_RETRY_AFTER_SECS)));
+
+        // a 403 for other reasons isn't retried
+        assert_eq!(retry_after(StatusCode::Forbidden, &headers(4000, reset), now), None);
+        assert_eq!(retry_after(StatusCode::Ok, &headers(0, reset), now), None);
+
+        // unless its body says otherwise
+        assert!(is_secondary_rate_limit(
+            r#"{"message": "You have exceeded a secondary rate limit."}"#));
+        assert!(!is_secondary_rate_limit(r#"{"message": "Must have admin rights to Repository."}"#));
+    }
+
+    #[test]
+    fn validators_from_headers() {
+        let mut h = Headers::new();
+        assert_eq!(Validators::from_headers(&h), None);
+
+        h.set(ETag("W/\"a5f3\"".to_string()));
+        assert_eq!(Validators::from_headers(&h),
+                   Some(Validators { etag: Some("W/\"a5f3\"".to_string()), last_modified: None }));
+
+        h.set(LastModified("Mon, 20 Aug 2018 12:00:00 GMT".to_string()));
+        assert_eq!(Validators::from_headers(&h).unwrap().last_modified,
+                   Some("Mon, 20 Aug 2018 12:00:00 GMT".to_string()));
+
+        // a 304 is the answer we were hoping for, not a rate limit
+        assert_eq!(retry_after(StatusCode::NotModified, &headers(0, Utc::now()), Utc::now()), None);
+    }
+}
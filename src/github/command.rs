@@ -77,12 +77,109 @@ impl FcpDisposition {
     }
 }
 
+/// All subcommands, for suggesting the closest one when we get a typo.
+const SUBCOMMANDS: &'static [&'static str] = &[
+    "merge", "close", "postpone", "cancel", "reviewed", "concern", "resolve",
+    "resolve-for", "transfer", "hold", "unhold", "poll", "change-disposition",
+];
+
+/// Find the subcommand which was most likely meant by `typo`, if any is close enough.
+pub fn closest_subcommand(typo: &str) -> Option<&'static str> {
+    let typo = typo.to_lowercase();
+    let max_distance = ::std::cmp::max(1, typo.chars().count() / 4);
+
+    SUBCOMMANDS.iter()
+        .map(|&subcommand| (edit_distance(&typo, subcommand), subcommand))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min()
+        .map(|(_, subcommand)| subcommand)
+}
+
+/// The number of insertions, deletions, substitutions and transpositions of
+/// adjacent characters needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    // d[i][j] is the distance between the first i chars of a and the first j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..a.len() + 1 {
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
 /// Parses the text of a subcommand.
 fn parse_command_text<'a>(command: &'a str, subcommand: &'a str) -> &'a str {
     let name_start = command.find(subcommand).unwrap() + subcommand.len();
     command[name_start..].trim()
 }
 
+/// Parses the text of a subcommand which starts with a user, e.g. `@bob NAME`,
+/// into the login of the user and the rest of the text.
+fn parse_user_and_text<'a>(text: &'a str) -> DashResult<(&'a str, &'a str)> {
+    let user = text.split_whitespace().next().unwrap_or("");
+    let login = user.trim_left_matches('@');
+    if login.is_empty() {
+        throw!(DashError::Misc(Some("no user specified".to_string())));
+    }
+
+    let name = parse_concern_name(&text[user.len()..])?;
+
+    Ok((login, name))
+}
+
+/// Parses the name of a concern, which is either the rest of the line or,
+/// if it starts with a quote, the quoted text. Anything after the closing
+/// quote is an explanation for humans, which we ignore.
+fn parse_concern_name<'a>(text: &'a str) -> DashResult<&'a str> {
+    let text = text.trim();
+
+    let name = match text.chars().next() {
+        Some(quote) if quote == '"' || quote == '`' => {
+            let quoted = &text[1..];
+            match quoted.find(quote) {
+                Some(end) => quoted[..end].trim(),
+                None => throw!(DashError::Misc(Some(format!(
+                    "the concern name {} is missing its closing quote", text)))),
+            }
+        }
+        _ => text,
+    };
+
+    if name.is_empty() {
+        throw!(DashError::Misc(Some("no concern specified".to_string())));
+    }
+
+    Ok(name)
+}
+
+/// The form of a concern name that's used to match it: lowercase, with
+/// runs of whitespace collapsed, and without surrounding quotes or trailing
+/// punctuation. `resolve Naming.` resolves the concern `naming`, for example.
+pub fn normalize_concern_name(name: &str) -> String {
+    name.trim_right_matches(|c: char| c.is_whitespace() || "\"`.,;:!?".contains(c))
+        .trim_left_matches(|c: char| c.is_whitespace() || c == '"' || c == '`')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
 fn strip_prefix<'h>(haystack: &'h str, prefix: &str) -> &'h str {
     haystack.find(prefix)
             .map(|idx| &haystack[idx + prefix.len()..])
@@ -90,6 +187,95 @@ fn strip_prefix<'h>(haystack: &'h str, prefix: &str) -> &'h str {
             .trim()
 }
 
+/// The lines of a comment that commands can be given on, i.e. those which
+/// aren't part of a code block or a quote. This follows the Markdown that
+/// GitHub renders closely enough for rfcbot's purposes, but doesn't know
+/// about lists (so an indented line after a list item counts as code).
+fn command_lines(body: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    // marker and length of the fence of the code block we're in
+    let mut fence = None;
+    // are we in a paragraph, which an indented line continues (rather than
+    // starting a code block), or in a quote, which a line continues lazily?
+    let mut in_paragraph = false;
+    let mut in_quote = false;
+
+    for line in body.lines() {
+        if let Some((marker, len)) = fence {
+            if closes_fence(line, marker, len) {
+                fence = None;
+            }
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            in_paragraph = false;
+            in_quote = false;
+        } else if let Some(opened) = opens_fence(line) {
+            fence = Some(opened);
+            in_paragraph = false;
+            in_quote = false;
+        } else if indentation(line) >= 4 && !in_paragraph {
+            // indented code
+        } else if indentation(line) < 4 && line.trim_left().starts_with('>') {
+            in_paragraph = true;
+            in_quote = true;
+        } else {
+            in_paragraph = true;
+            if !in_quote {
+                lines.push(line);
+            }
+        }
+    }
+
+    lines
+}
+
+/// How many columns a line is indented by, with tab stops of 4.
+fn indentation(line: &str) -> usize {
+    let mut columns = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => columns += 1,
+            '\t' => columns += 4 - columns % 4,
+            _ => break,
+        }
+    }
+    columns
+}
+
+/// If the line opens a fenced code block, its fence marker and length.
+fn opens_fence(line: &str) -> Option<(char, usize)> {
+    if indentation(line) >= 4 {
+        return None;
+    }
+
+    let line = line.trim_left();
+    let marker = match line.chars().next() {
+        Some(c) if c == '`' || c == '~' => c,
+        _ => return None,
+    };
+    let len = line.chars().take_while(|&c| c == marker).count();
+
+    // the info string of a backtick fence can't contain backticks
+    if len < 3 || (marker == '`' && line[len..].contains('`')) {
+        None
+    } else {
+        Some((marker, len))
+    }
+}
+
+/// Does the line close a fenced code block opened by `len` of `marker`?
+fn closes_fence(line: &str, marker: char, len: usize) -> bool {
+    if indentation(line) >= 4 {
+        return false;
+    }
+
+    let line = line.trim();
+    let run = line.chars().take_while(|&c| c == marker).count();
+    run >= len && run == line.chars().count()
+}
+
 fn match_team_candidate<'a>
     (setup: &'a RfcbotConfig, team_candidate: &str)
     -> Option<&'a TeamLabel>
@@ -100,6 +286,39 @@ fn match_team_candidate<'a>
     }).map(|(label, _)| label)
 }
 
+/// Like `match_team_candidate`, but only for a team's full label or its
+/// ping with the `@`. What follows the disposition of an FCP proposal is
+/// usually its justification, whose first word could well be the short
+/// name of a team ("close, release notes are wrong").
+fn match_fcp_team_target<'a>
+    (setup: &'a RfcbotConfig, team_candidate: &str)
+    -> Option<&'a TeamLabel>
+{
+    setup.teams().find(|&(label, team)| {
+        label.0 == team_candidate ||
+        team_candidate.starts_with('@') && team.ping() == &team_candidate[1..]
+    }).map(|(label, _)| label)
+}
+
+/// Parses the teams at the start of a subcommand's text, as recognized by
+/// `matches`, returning their labels and the rest of the text.
+fn parse_team_targets<'a, F>(setup: &'a RfcbotConfig, text: &'a str, matches: F)
+    -> (BTreeSet<&'a str>, &'a str)
+    where F: Fn(&'a RfcbotConfig, &str) -> Option<&'a TeamLabel>
+{
+    let mut rest = text;
+    let mut teams = BTreeSet::new();
+    while let Some(team_candidate) = rest.split_whitespace().next() {
+        if let Some(team) = matches(setup, team_candidate) {
+            rest = parse_command_text(rest, team_candidate);
+            teams.insert(&*team.0);
+        } else {
+            break;
+        }
+    }
+    (teams, rest)
+}
+
 /// Parses all subcommands under the fcp command.
 /// If `fcp_context` is set to false, `@rfcbot <subcommand>`
 /// was passed and not `@rfcbot fcp <subcommand>`.
@@ -113,6 +332,10 @@ fn match_team_candidate<'a>
 /// review ::= "reviewed" | "review" | "reviewing" | "reviews" ;
 /// concern ::= "concern" | "concerned" | "concerning" | "concerns" ;
 /// resolve ::= "resolve" | "resolved" | "resolving" | "resolves" ;
+/// resolve_for ::= "resolve-for" ;
+/// transfer ::= "transfer" | "transferred" | "transferring" | "transfers" ;
+/// hold ::= "hold" | "held" | "holding" | "holds" ;
+/// unhold ::= "unhold" | "unheld" | "unholding" | "unholds" ;
 /// poll ::=  "ask" | "asked" | "asking" | "asks" |
 ///          "poll" | "polled" | "polling" | "polls" |
 ///          "query" | "queried" | "querying" | "queries" |
@@ -124,13 +347,25 @@ fn match_team_candidate<'a>
 /// team_label_simple ::= "lang" | .. ;
 /// team_ping ::= "@"? "rust-lang/lang" | ..;
 /// team_target ::= team_label | team_label_simple | team_ping ;
+/// fcp_team_target ::= team_label | "@" team_ping ;
 ///
+/// user ::= "@"? login ;
 /// line_remainder ::= .+$ ;
 /// ws_separated ::= ... ;
 ///
-/// subcommand ::= merge | close | postpone | cancel | review
+/// disposition ::= "merge" | "close" | "postpone" ;
+///
+/// subcommand ::= merge [fcp_team_target]*
+///              | close [fcp_team_target]*
+///              | postpone [fcp_team_target]*
+///              | cancel | review
+///              | "change-disposition" disposition
 ///              | concern line_remainder
 ///              | resolve line_remainder
+///              | resolve_for user line_remainder
+///              | transfer user line_remainder
+///              | hold line_remainder
+///              | unhold
 ///              | poll [team_target]* line_remainder
 ///              ;
 ///
@@ -149,21 +384,46 @@ fn parse_fcp_subcommand<'a>(
 ) -> DashResult<RfcBotCommand<'a>> {
     Ok(match subcommand {
         // Parse a FCP merge command:
-        "merge" | "merged" | "merging" | "merges" =>
-            RfcBotCommand::FcpPropose(FcpDisposition::Merge),
+        "merge" | "merged" | "merging" | "merges" => {
+            let (teams, _) = parse_team_targets(setup,
+                                               parse_command_text(command, subcommand),
+                                               match_fcp_team_target);
+            RfcBotCommand::FcpPropose(FcpDisposition::Merge, teams)
+        },
 
         // Parse a FCP close command:
-        "close" | "closed" | "closing" | "closes" =>
-            RfcBotCommand::FcpPropose(FcpDisposition::Close),
+        "close" | "closed" | "closing" | "closes" => {
+            let (teams, _) = parse_team_targets(setup,
+                                               parse_command_text(command, subcommand),
+                                               match_fcp_team_target);
+            RfcBotCommand::FcpPropose(FcpDisposition::Close, teams)
+        },
 
         // Parse a FCP postpone command:
-        "postpone" | "postponed" | "postponing" | "postpones" =>
-            RfcBotCommand::FcpPropose(FcpDisposition::Postpone),
+        "postpone" | "postponed" | "postponing" | "postpones" => {
+            let (teams, _) = parse_team_targets(setup,
+                                               parse_command_text(command, subcommand),
+                                               match_fcp_team_target);
+            RfcBotCommand::FcpPropose(FcpDisposition::Postpone, teams)
+        },
 
         // Parse a FCP cancel command:
         "cancel" | "canceled" | "canceling" | "cancels" =>
             RfcBotCommand::FcpCancel,
 
+        // Parse a FCP change-disposition command:
+        "change-disposition" => {
+            debug!("Parsed command as ChangeDisposition");
+            let disposition = parse_command_text(command, subcommand)
+                .split_whitespace()
+                .next()
+                .and_then(|disposition| FcpDisposition::from_str(disposition).ok())
+                .ok_or_else(|| DashError::Misc(Some(
+                    "`change-disposition` needs one of `merge`, `close` or `postpone`"
+                        .to_string())))?;
+            RfcBotCommand::ChangeDisposition(disposition)
+        },
+
         // Parse a FCP reviewed command:
         "reviewed" | "review" | "reviewing" | "reviews" =>
             RfcBotCommand::Reviewed,
@@ -171,15 +431,39 @@ fn parse_fcp_subcommand<'a>(
         // Parse a FCP concern command:
         "concern" | "concerned" | "concerning" | "concerns" => {
             debug!("Parsed command as NewConcern");
-            RfcBotCommand::NewConcern(parse_command_text(command, subcommand))
+            RfcBotCommand::NewConcern(parse_concern_name(parse_command_text(command, subcommand))?)
         },
 
         // Parse a FCP resolve command:
         "resolve" | "resolved" | "resolving" | "resolves" => {
             debug!("Parsed command as ResolveConcern");
-            RfcBotCommand::ResolveConcern(parse_command_text(command, subcommand))
+            RfcBotCommand::ResolveConcern(parse_concern_name(parse_command_text(command, subcommand))?)
+        },
+
+        // Parse a FCP resolve-for command, only accepted from team leads:
+        "resolve-for" => {
+            debug!("Parsed command as ResolveConcernFor");
+            let (user, name) = parse_user_and_text(parse_command_text(command, subcommand))?;
+            RfcBotCommand::ResolveConcernFor { user, name }
         },
 
+        // Parse a FCP transfer command, only accepted from team leads:
+        "transfer" | "transferred" | "transferring" | "transfers" => {
+            debug!("Parsed command as TransferConcern");
+            let (user, name) = parse_user_and_text(parse_command_text(command, subcommand))?;
+            RfcBotCommand::TransferConcern { user, name }
+        },
+
+        // Parse a FCP hold command:
+        "hold" | "held" | "holding" | "holds" => {
+            debug!("Parsed command as Hold");
+            RfcBotCommand::Hold(parse_command_text(command, subcommand))
+        },
+
+        // Parse a FCP unhold command:
+        "unhold" | "unheld" | "unholding" | "unholds" =>
+            RfcBotCommand::Unhold,
+
         // Parse a StartPoll command:
         "ask" | "asked" | "asking" | "asks" |
         "poll" | "polled" | "polling" | "polls" |
@@ -189,25 +473,24 @@ fn parse_fcp_subcommand<'a>(
         "survey" | "surveyed" | "surveying" | "surveys" => {
             debug!("Parsed command as StartPoll");
 
-            let mut question = parse_command_text(command, subcommand);
-            let mut teams = BTreeSet::new();
-            while let Some(team_candidate) = question.split_whitespace().next() {
-                if let Some(team) = match_team_candidate(setup, team_candidate) {
-                    question = parse_command_text(question, team_candidate);
-                    teams.insert(&*team.0);
-                } else {
-                    break;
-                }
-            }
+            let (teams, question) = parse_team_targets(setup,
+                                                       parse_command_text(command, subcommand),
+                                                       match_team_candidate);
             RfcBotCommand::StartPoll { teams, question }
         },
 
         _ => {
-            throw!(DashError::Misc(if fcp_context {
-                error!("unrecognized subcommand for fcp: {}", subcommand);
-                Some(format!("found bad subcommand: {}", subcommand))
-            } else {
-                None
+            // Outside of `fcp` we might just be mentioned in a sentence,
+            // so only complain if it looks like a typo.
+            throw!(DashError::Misc(match (closest_subcommand(subcommand), fcp_context) {
+                (Some(suggestion), _) =>
+                    Some(format!("unknown subcommand `{}`, did you mean `{}`?",
+                                 subcommand, suggestion)),
+                (None, true) => {
+                    error!("unrecognized subcommand for fcp: {}", subcommand);
+                    Some(format!("unknown subcommand `{}`", subcommand))
+                }
+                (None, false) => None,
             }))
         }
     })
@@ -222,7 +505,8 @@ fn from_invocation_line<'a>
     let invocation = tokens.next().ok_or(DashError::Misc(None))?;
     match invocation {
         "fcp" | "pr" => {
-            let subcommand = tokens.next().ok_or(DashError::Misc(None))?;
+            let subcommand = tokens.next().ok_or_else(|| DashError::Misc(Some(
+                format!("`{}` needs a subcommand, e.g. `{} merge`", invocation, invocation))))?;
 
             debug!("Parsed command as new FCP proposal");
 
@@ -246,11 +530,22 @@ fn from_invocation_line<'a>
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum RfcBotCommand<'a> {
-    FcpPropose(FcpDisposition),
+    FcpPropose(FcpDisposition, BTreeSet<&'a str>),
     FcpCancel,
+    ChangeDisposition(FcpDisposition),
     Reviewed,
     NewConcern(&'a str),
     ResolveConcern(&'a str),
+    ResolveConcernFor {
+        user: &'a str,
+        name: &'a str,
+    },
+    TransferConcern {
+        user: &'a str,
+        name: &'a str,
+    },
+    Hold(&'a str),
+    Unhold,
     FeedbackRequest(&'a str),
     StartPoll {
         teams: BTreeSet<&'a str>,
@@ -262,12 +557,42 @@ impl<'a> RfcBotCommand<'a> {
     pub fn from_str_all(setup: &'a RfcbotConfig, command: &'a str)
         -> impl Iterator<Item = RfcBotCommand<'a>>
     {
-        // Get the tokens for each command line (starts with a bot mention)
-        command.lines()
+        Self::parse_all(setup, command).filter_map(Result::ok)
+    }
+
+    /// Parse each command line (starts with a bot mention, and isn't code or
+    /// quoted) of a comment.
+    /// Errors with a message are meant to be shown to the author of the
+    /// comment, the others are lines which weren't meant for us.
+    pub fn parse_all(setup: &'a RfcbotConfig, command: &'a str)
+        -> impl Iterator<Item = DashResult<RfcBotCommand<'a>>>
+    {
+        command_lines(command)
+               .into_iter()
                .map(|l| l.trim())
                .filter(|&l| l.starts_with(RFC_BOT_MENTION))
                .map(move |l| from_invocation_line(setup, l))
-               .filter_map(Result::ok)
+    }
+
+    /// How the command is invoked, for messages about it.
+    pub fn keyword(&self) -> &'static str {
+        use self::RfcBotCommand::*;
+        match *self {
+            FcpPropose(FcpDisposition::Merge, _) => "fcp merge",
+            FcpPropose(FcpDisposition::Close, _) => "fcp close",
+            FcpPropose(FcpDisposition::Postpone, _) => "fcp postpone",
+            FcpCancel => "fcp cancel",
+            ChangeDisposition(_) => "fcp change-disposition",
+            Reviewed => "reviewed",
+            NewConcern(_) => "concern",
+            ResolveConcern(_) => "resolve",
+            ResolveConcernFor { .. } => "resolve-for",
+            TransferConcern { .. } => "transfer",
+            Hold(_) => "hold",
+            Unhold => "unhold",
+            FeedbackRequest(_) => "f?",
+            StartPoll { .. } => "poll",
+        }
     }
 }
 
@@ -322,6 +647,104 @@ foobar
         ]);
     }
 
+    #[test]
+    fn ignore_fenced_code() {
+let text = r#"
+To propose merging, use:
+
+```
+@rfcbot fcp merge
+```
+
+~~~~markdown
+@rfcbot concern in-tildes
+~~~
+@rfcbot concern still-in-tildes
+~~~~
+
+@rfcbot concern after-code
+"#;
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::NewConcern("after-code"),
+        ]);
+    }
+
+    #[test]
+    fn unclosed_fence_runs_to_the_end() {
+let text = r#"
+@rfcbot reviewed
+
+```rust
+@rfcbot fcp merge
+"#;
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::Reviewed,
+        ]);
+    }
+
+    #[test]
+    fn backticks_in_info_string_dont_open_a_fence() {
+let text = r#"
+``` not a fence ```
+@rfcbot reviewed
+"#;
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::Reviewed,
+        ]);
+    }
+
+    #[test]
+    fn ignore_indented_code() {
+let text = "
+For example:
+
+    @rfcbot fcp merge
+\t@rfcbot concern tabbed
+
+@rfcbot concern after-code
+";
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::NewConcern("after-code"),
+        ]);
+    }
+
+    #[test]
+    fn ignore_quotes() {
+let text = r#"
+> @rfcbot fcp merge
+>
+>> @rfcbot concern nested
+   > @rfcbot concern indented-quote
+lazily continued
+@rfcbot concern lazily-continued
+
+@rfcbot concern after-quote
+"#;
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::NewConcern("after-quote"),
+        ]);
+    }
+
+    #[test]
+    fn quoted_reply() {
+let text = r#"
+> I have a concern about this.
+>
+> @rfcbot concern naming
+
+@rfcbot resolved naming
+"#;
+
+        assert_eq!(parse_commands(text).collect::<Vec<_>>(), vec![
+            RfcBotCommand::ResolveConcern("naming"),
+        ]);
+    }
+
     #[test]
     fn fix_issue_225() {
 let text = r#"
@@ -398,21 +821,43 @@ somemoretext")
          "fcp merge", "fcp merged", "fcp merging", "fcp merges",
          "pr merge", "pr merged", "pr merging", "pr merges"],
         justification!(),
-        RfcBotCommand::FcpPropose(FcpDisposition::Merge));
+        RfcBotCommand::FcpPropose(FcpDisposition::Merge, BTreeSet::new()));
 
     test_from_str!(success_fcp_close,
         ["close", "closed", "closing", "closes",
          "fcp close", "fcp closed", "fcp closing", "fcp closes",
          "pr close", "pr closed", "pr closing", "pr closes"],
         justification!(),
-        RfcBotCommand::FcpPropose(FcpDisposition::Close));
+        RfcBotCommand::FcpPropose(FcpDisposition::Close, BTreeSet::new()));
 
     test_from_str!(success_fcp_postpone,
         ["postpone", "postponed", "postponing", "postpones",
          "fcp postpone", "fcp postponed", "fcp postponing", "fcp postpones",
          "pr postpone", "pr postponed", "pr postponing", "pr postpones"],
         justification!(),
-        RfcBotCommand::FcpPropose(FcpDisposition::Postpone));
+        RfcBotCommand::FcpPropose(FcpDisposition::Postpone, BTreeSet::new()));
+
+    test_from_str!(success_fcp_merge_for_teams,
+        ["merge", "fcp merge", "pr merge"],
+        some_text!("T-avengers @dc-comics/justice-league"),
+        RfcBotCommand::FcpPropose(FcpDisposition::Merge, btreeset! {
+            "T-avengers",
+            "justice-league",
+        }));
+
+    test_from_str!(success_fcp_close_for_teams,
+        ["close", "fcp close", "pr close"],
+        some_text!("T-avengers as discussed"),
+        RfcBotCommand::FcpPropose(FcpDisposition::Close, btreeset! {
+            "T-avengers",
+        }));
+
+    // a justification starting with a team's short name or unprefixed ping
+    // doesn't scope the proposal to that team
+    test_from_str!(success_fcp_close_with_justification_naming_a_team,
+        ["close", "fcp close", "pr close"],
+        some_text!("avengers dc-comics/justice-league: the release notes are wrong"),
+        RfcBotCommand::FcpPropose(FcpDisposition::Close, BTreeSet::new()));
 
     test_from_str!(success_fcp_cancel,
         ["cancel", "canceled", "canceling", "cancels",
@@ -421,6 +866,20 @@ somemoretext")
         justification!(),
         RfcBotCommand::FcpCancel);
 
+    test_from_str!(success_change_disposition,
+        ["change-disposition", "fcp change-disposition", "pr change-disposition"],
+        some_text!("close"),
+        RfcBotCommand::ChangeDisposition(FcpDisposition::Close));
+
+    #[test]
+    fn change_disposition_needs_disposition() {
+        let needs = Some("`change-disposition` needs one of `merge`, `close` or `postpone`"
+                         .to_string());
+        assert_eq!(parse_error("@rfcbot fcp change-disposition"), needs);
+        assert_eq!(parse_error("@rfcbot fcp change-disposition frobnicate"), needs);
+        assert_eq!(parse_error("@rfcbot fcp change-disposition mrege"), needs);
+    }
+
     test_from_str!(success_concern,
         ["concern", "concerned", "concerning", "concerns",
          "fcp concern", "fcp concerned", "fcp concerning", "fcp concerns",
@@ -435,6 +894,66 @@ somemoretext")
         some_text!("CONCERN_NAME"),
         RfcBotCommand::ResolveConcern("CONCERN_NAME"));
 
+    test_from_str!(success_resolve_for,
+        ["resolve-for", "fcp resolve-for", "pr resolve-for"],
+        some_text!("@bob CONCERN_NAME"),
+        RfcBotCommand::ResolveConcernFor { user: "bob", name: "CONCERN_NAME" });
+
+    test_from_str!(success_transfer,
+        ["transfer", "transferred", "transferring", "transfers",
+         "fcp transfer", "fcp transferred", "fcp transferring", "fcp transfers",
+         "pr transfer", "pr transferred", "pr transferring", "pr transfers"],
+        some_text!("@bob CONCERN_NAME"),
+        RfcBotCommand::TransferConcern { user: "bob", name: "CONCERN_NAME" });
+
+    #[test]
+    fn resolve_for_needs_user_and_concern() {
+        assert!(parse_commands("@rfcbot resolve-for").next().is_none());
+        assert!(parse_commands("@rfcbot resolve-for @bob").next().is_none());
+        assert!(parse_commands("@rfcbot transfer @ CONCERN_NAME").next().is_none());
+    }
+
+    #[test]
+    fn quoted_concern_names() {
+        assert_eq!(parse_commands("@rfcbot concern \"naming things\" it's hard").next(),
+                   Some(RfcBotCommand::NewConcern("naming things")));
+        assert_eq!(parse_commands("@rfcbot resolve `naming things`, thanks!").next(),
+                   Some(RfcBotCommand::ResolveConcern("naming things")));
+        assert_eq!(parse_commands("@rfcbot resolve-for @bob \"naming things\" as discussed").next(),
+                   Some(RfcBotCommand::ResolveConcernFor { user: "bob", name: "naming things" }));
+        assert_eq!(parse_commands("@rfcbot concern naming things").next(),
+                   Some(RfcBotCommand::NewConcern("naming things")));
+
+        assert_eq!(parse_error("@rfcbot concern \"naming things"),
+                   Some("the concern name \"naming things is missing its closing quote".to_string()));
+        assert_eq!(parse_error("@rfcbot concern \"\" empty"),
+                   Some("no concern specified".to_string()));
+        assert_eq!(parse_error("@rfcbot resolve"),
+                   Some("no concern specified".to_string()));
+    }
+
+    #[test]
+    fn normalized_concern_names() {
+        assert_eq!(normalize_concern_name("naming"), "naming");
+        assert_eq!(normalize_concern_name(" Naming. "), "naming");
+        assert_eq!(normalize_concern_name("Naming   Things?!"), "naming things");
+        assert_eq!(normalize_concern_name("\"naming\"."), "naming");
+        assert_eq!(normalize_concern_name("`a.b`"), "a.b");
+    }
+
+    test_from_str!(success_hold,
+        ["hold", "held", "holding", "holds",
+         "fcp hold", "fcp held", "fcp holding", "fcp holds",
+         "pr hold", "pr held", "pr holding", "pr holds"],
+        some_text!("waiting for the survey results"),
+        RfcBotCommand::Hold("waiting for the survey results"));
+
+    test_from_str!(success_unhold,
+        ["unhold", "unheld", "unholding", "unholds",
+         "fcp unhold", "fcp unheld", "fcp unholding", "fcp unholds",
+         "pr unhold", "pr unheld", "pr unholding", "pr unholds"],
+        RfcBotCommand::Unhold);
+
     test_from_str!(success_ask_question,
         ["ask", "asked", "asking", "asks",
          "poll", "polled", "polling", "polls",
@@ -486,4 +1005,35 @@ somemoretext";
 
     test_from_str!(success_feedback, ["f?"], some_text!("@bob"),
         RfcBotCommand::FeedbackRequest("bob"));
+
+    fn parse_error(body: &str) -> Option<String> {
+        match ensure_take_singleton(RfcBotCommand::parse_all(&TEST_SETUP, body)) {
+            Err(DashError::Misc(message)) => message,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn suggest_closest_subcommand() {
+        assert_eq!(closest_subcommand("mrege"), Some("merge"));
+        assert_eq!(closest_subcommand("reviewd"), Some("reviewed"));
+        assert_eq!(closest_subcommand("Concren"), Some("concern"));
+        assert_eq!(closest_subcommand("unhodl"), Some("unhold"));
+        assert_eq!(closest_subcommand("is"), None);
+        assert_eq!(closest_subcommand("frobnicate"), None);
+    }
+
+    #[test]
+    fn parse_errors_explain_themselves() {
+        assert_eq!(parse_error("@rfcbot fcp mrege"),
+                   Some("unknown subcommand `mrege`, did you mean `merge`?".to_string()));
+        assert_eq!(parse_error("@rfcbot mrege"),
+                   Some("unknown subcommand `mrege`, did you mean `merge`?".to_string()));
+        assert_eq!(parse_error("@rfcbot fcp frobnicate"),
+                   Some("unknown subcommand `frobnicate`".to_string()));
+        assert!(parse_error("@rfcbot fcp").is_some());
+
+        // we're probably just being talked about
+        assert_eq!(parse_error("@rfcbot is a bot"), None);
+    }
 }
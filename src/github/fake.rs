@@ -0,0 +1,308 @@
+//! An in-memory stand-in for the GitHub API.
+//!
+//! `RecordingGitHub` keeps issues, comments and labels in memory and records
+//! every mutation made through it, so that tests can drive complete FCP flows
+//! (propose -> review -> FCP -> FFCP) and then inspect what the bot did.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde_json;
+
+use domain::github::GitHubUser;
+use error::{DashError, DashResult};
+use github::client::{Fetched, GitHubApi, Validators};
+use github::models::{CommentFromJson, IssueFromJson, PullRequestFromJson, PullRequestUrls};
+
+/// The user all comments posted through the fake are attributed to.
+pub const BOT_USER_ID: i32 = -42;
+pub const BOT_USER_LOGIN: &'static str = "rfcbot-fake";
+
+/// Comment ids handed out by the fake start here, far away from real ids
+/// and from the ids tests pick for their own comments.
+const FIRST_COMMENT_ID: i32 = -2_000_000_000;
+
+/// A mutation performed through the fake.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Call {
+    NewComment { repo: String, issue_num: i32, text: String },
+    EditComment { repo: String, comment_num: i32, text: String },
+    AddLabel { repo: String, issue_num: i32, label: String },
+    RemoveLabel { repo: String, issue_num: i32, label: String },
+    CloseIssue { repo: String, issue_num: i32 },
+}
+
+impl Call {
+    fn repo(&self) -> &str {
+        match *self {
+            Call::NewComment { ref repo, .. } |
+            Call::EditComment { ref repo, .. } |
+            Call::AddLabel { ref repo, .. } |
+            Call::RemoveLabel { ref repo, .. } |
+            Call::CloseIssue { ref repo, .. } => repo,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FakeComment {
+    id: i32,
+    repo: String,
+    issue_num: i32,
+    body: String,
+    user: GitHubUser,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl FakeComment {
+    fn to_json(&self) -> CommentFromJson {
+        CommentFromJson {
+            id: self.id,
+            html_url: format!("https://github.com/{}/issues/{}#issuecomment-{}",
+                              self.repo,
+                              self.issue_num,
+                              self.id),
+            body: self.body.clone(),
+            user: self.user.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    calls: Vec<Call>,
+    next_comment_id: i32,
+    comments: BTreeMap<i32, FakeComment>,
+    issues: BTreeMap<String, Vec<serde_json::Value>>,
+    labels: BTreeMap<(String, i32), BTreeSet<String>>,
+    closed: BTreeSet<(String, i32)>,
+    not_modified: BTreeMap<String, usize>,
+}
+
+#[derive(Debug)]
+pub struct RecordingGitHub {
+    state: Mutex<State>,
+}
+
+impl RecordingGitHub {
+    pub fn new() -> Self {
+        RecordingGitHub {
+            state: Mutex::new(State {
+                next_comment_id: FIRST_COMMENT_ID,
+                ..State::default()
+            }),
+        }
+    }
+
+    /// The user that comments posted through the fake are attributed to.
+    /// Tests need to insert it into the database before driving the bot.
+    pub fn bot_user(&self) -> GitHubUser {
+        GitHubUser {
+            id: BOT_USER_ID,
+            login: BOT_USER_LOGIN.to_string(),
+        }
+    }
+
+    /// Make an issue (given as the JSON GitHub would return for it) visible
+    /// to `issues_since`.
+    pub fn seed_issue(&self, repo: &str, issue: serde_json::Value) {
+        let mut state = self.state.lock().unwrap();
+        state.issues.entry(repo.to_string()).or_insert_with(Vec::new).push(issue);
+    }
+
+    /// All mutations performed against issues in `repo`, oldest first.
+    pub fn calls_for(&self, repo: &str) -> Vec<Call> {
+        let state = self.state.lock().unwrap();
+        state.calls.iter().filter(|c| c.repo() == repo).cloned().collect()
+    }
+
+    /// The labels currently set on an issue.
+    pub fn labels(&self, repo: &str, issue_num: i32) -> BTreeSet<String> {
+        let state = self.state.lock().unwrap();
+        state.labels.get(&(repo.to_string(), issue_num)).cloned().unwrap_or_default()
+    }
+
+    /// Has the issue been closed through the fake?
+    pub fn is_closed(&self, repo: &str, issue_num: i32) -> bool {
+        let state = self.state.lock().unwrap();
+        state.closed.contains(&(repo.to_string(), issue_num))
+    }
+
+    /// How many listings of `repo` were answered with "not modified".
+    pub fn not_modified(&self, repo: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state.not_modified.get(repo).cloned().unwrap_or(0)
+    }
+
+    /// The bodies of all comments posted on an issue, oldest first.
+    pub fn comment_bodies(&self, repo: &str, issue_num: i32) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.comments
+             .values()
+             .filter(|c| c.repo == repo && c.issue_num == issue_num)
+             .map(|c| c.body.clone())
+             .collect()
+    }
+}
+
+impl GitHubApi for RecordingGitHub {
+    fn org_repos(&self, org: &str) -> DashResult<Vec<String>> {
+        let state = self.state.lock().unwrap();
+        let prefix = format!("{}/", org);
+        let repos = state.issues.keys()
+            .chain(state.comments.values().map(|c| &c.repo))
+            .filter(|r| r.starts_with(&prefix))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        Ok(repos.into_iter().collect())
+    }
+
+    fn issues_since(&self,
+                    repo: &str,
+                    start: DateTime<Utc>,
+                    cached: Option<&Validators>)
+                    -> DashResult<Fetched<Vec<IssueFromJson>>> {
+        let mut state = self.state.lock().unwrap();
+        let mut issues = Vec::new();
+        for value in state.issues.get(repo).into_iter().flat_map(|is| is.iter()) {
+            let issue: IssueFromJson = serde_json::from_value(value.clone())?;
+            if issue.updated_at >= start {
+                issues.push(issue);
+            }
+        }
+        Ok(conditional(&mut state, repo, issues, cached))
+    }
+
+    fn comments_since(&self,
+                      repo: &str,
+                      start: DateTime<Utc>,
+                      cached: Option<&Validators>)
+                      -> DashResult<Fetched<Vec<CommentFromJson>>> {
+        let mut state = self.state.lock().unwrap();
+        let comments = state.comments
+                            .values()
+                            .filter(|c| c.repo == repo && c.updated_at >= start)
+                            .map(FakeComment::to_json)
+                            .collect::<Vec<_>>();
+        Ok(conditional(&mut state, repo, comments, cached))
+    }
+
+    fn fetch_pull_request(&self, _: &PullRequestUrls) -> DashResult<PullRequestFromJson> {
+        throw!(DashError::Misc(Some("pull requests aren't supported by the fake".to_string())))
+    }
+
+    fn close_issue(&self, repo: &str, issue_num: i32) -> DashResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(Call::CloseIssue {
+            repo: repo.to_string(),
+            issue_num,
+        });
+        state.closed.insert((repo.to_string(), issue_num));
+        Ok(())
+    }
+
+    fn add_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(Call::AddLabel {
+            repo: repo.to_string(),
+            issue_num,
+            label: label.to_string(),
+        });
+        state.labels
+             .entry((repo.to_string(), issue_num))
+             .or_insert_with(BTreeSet::new)
+             .insert(label.to_string());
+        Ok(())
+    }
+
+    fn remove_label(&self, repo: &str, issue_num: i32, label: &str) -> DashResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(Call::RemoveLabel {
+            repo: repo.to_string(),
+            issue_num,
+            label: label.to_string(),
+        });
+        // GitHub responds with a 404 if the label isn't there, which the
+        // client treats as success
+        if let Some(labels) = state.labels.get_mut(&(repo.to_string(), issue_num)) {
+            labels.remove(label);
+        }
+        Ok(())
+    }
+
+    fn new_comment(&self, repo: &str, issue_num: i32, text: &str) -> DashResult<CommentFromJson> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(Call::NewComment {
+            repo: repo.to_string(),
+            issue_num,
+            text: text.to_string(),
+        });
+
+        let now = Utc::now();
+        let comment = FakeComment {
+            id: state.next_comment_id,
+            repo: repo.to_string(),
+            issue_num,
+            body: text.to_string(),
+            user: self.bot_user(),
+            created_at: now,
+            updated_at: now,
+        };
+        state.next_comment_id += 1;
+
+        let json = comment.to_json();
+        state.comments.insert(comment.id, comment);
+        Ok(json)
+    }
+
+    fn edit_comment(&self,
+                    repo: &str,
+                    comment_num: i32,
+                    text: &str)
+                    -> DashResult<CommentFromJson> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(Call::EditComment {
+            repo: repo.to_string(),
+            comment_num,
+            text: text.to_string(),
+        });
+
+        match state.comments.get_mut(&comment_num) {
+            Some(comment) => {
+                comment.body = text.to_string();
+                comment.updated_at = Utc::now();
+                Ok(comment.to_json())
+            }
+            None => throw!(DashError::Misc(Some(format!("no comment {}", comment_num)))),
+        }
+    }
+}
+
+/// Answer a listing like GitHub would answer a conditional request for it,
+/// with an ETag derived from the contents of the response.
+fn conditional<T: Debug>(state: &mut State,
+                         repo: &str,
+                         models: Vec<T>,
+                         cached: Option<&Validators>)
+                         -> Fetched<Vec<T>> {
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}", models).hash(&mut hasher);
+    let etag = format!("\"{:x}\"", hasher.finish());
+
+    if cached.and_then(|c| c.etag.as_ref()) == Some(&etag) {
+        *state.not_modified.entry(repo.to_string()).or_insert(0) += 1;
+        return Fetched::NotModified;
+    }
+
+    Fetched::Modified(models, Some(Validators {
+        etag: Some(etag),
+        last_modified: None,
+    }))
+}
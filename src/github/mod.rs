@@ -3,10 +3,16 @@
 
 pub mod client;
 pub mod models;
-mod command;
+pub mod command;
+#[cfg(test)]
+pub mod fake;
 mod nag;
+pub mod outbox;
 pub mod webhooks;
 
+#[cfg(test)]
+pub use self::nag::test::Harness;
+
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use diesel::prelude::*;
 use diesel::pg::PgConnection;
@@ -15,13 +21,28 @@ use diesel;
 use DB_POOL;
 use domain::github::*;
 use domain::schema::*;
-use error::DashResult;
+use error::{DashError, DashResult};
 
-use self::client::Client;
+use self::client::{Fetched, GitHubApi, Validators};
 use self::models::{CommentFromJson, IssueFromJson, PullRequestFromJson};
 
+/// The GitHub API implementation used by the bot.
+#[cfg(not(test))]
+pub type GitHub = self::client::Client;
+
+/// In tests we never talk to api.github.com, everything is recorded in memory instead.
+#[cfg(test)]
+pub type GitHub = self::fake::RecordingGitHub;
+
 lazy_static! {
-    pub static ref GH: Client = Client::new();
+    pub static ref GH: GitHub = GitHub::new();
+}
+
+/// Should the bot post comments and change labels on GitHub?
+/// Always true in tests, where `GH` is an in-memory fake.
+pub fn posting_enabled() -> bool {
+    use config::CONFIG;
+    cfg!(test) || CONFIG.post_comments
 }
 
 pub fn most_recent_update() -> DashResult<DateTime<Utc>> {
@@ -45,30 +66,153 @@ pub fn most_recent_update() -> DashResult<DateTime<Utc>> {
     Ok(DateTime::from_utc(updated, Utc))
 }
 
-pub fn record_successful_update(ingest_start: NaiveDateTime) -> DashResult<()> {
+/// Record a scrape of all repositories, which was only successful if none of
+/// the `failed` repositories failed to sync.
+pub fn record_update(ingest_start: NaiveDateTime, failed: &[String]) -> DashResult<()> {
     let conn = &*DB_POOL.get()?;
-    // insert a successful sync record
     use domain::schema::githubsync::dsl::*;
     let sync_record = GitHubSyncPartial {
-        successful: true,
+        successful: failed.is_empty(),
         ran_at: ingest_start,
-        message: None,
+        message: if failed.is_empty() {
+            None
+        } else {
+            Some(format!("unable to sync {}", failed.join(", ")))
+        },
     };
 
     diesel::insert_into(githubsync).values(&sync_record).execute(conn)?;
     Ok(())
 }
 
+/// Since when to fetch changes in `repo`: the start of its last successful
+/// sync, or `fallback` if it was never synced successfully on its own.
+pub fn sync_cursor(repo: &str, fallback: DateTime<Utc>) -> DashResult<DateTime<Utc>> {
+    let conn = &*DB_POOL.get()?;
+    let synced_until = github_sync_cursor::table
+        .find(repo)
+        .select(github_sync_cursor::synced_until)
+        .first::<Option<NaiveDateTime>>(conn)
+        .optional()?;
+
+    Ok(match synced_until {
+        Some(Some(until)) => DateTime::from_utc(until, Utc),
+        _ => fallback,
+    })
+}
+
+/// Record the outcome of syncing `repo`, which started at `ingest_start`.
+/// Only a successful sync moves the repository's cursor forward.
+pub fn record_sync(repo: &str, ingest_start: NaiveDateTime, result: &DashResult<()>) -> DashResult<()> {
+    let conn = &*DB_POOL.get()?;
+    let previous = github_sync_cursor::table
+        .find(repo)
+        .first::<GitHubSyncCursor>(conn)
+        .optional()?;
+
+    let cursor = match (previous, result) {
+        (_, &Ok(())) => GitHubSyncCursor {
+            repository: repo.to_string(),
+            synced_until: Some(ingest_start),
+            last_attempt_at: ingest_start,
+            successful: true,
+            message: None,
+            failures: 0,
+        },
+        (previous, &Err(ref why)) => GitHubSyncCursor {
+            repository: repo.to_string(),
+            synced_until: previous.as_ref().and_then(|p| p.synced_until),
+            last_attempt_at: ingest_start,
+            successful: false,
+            message: Some(format!("{:?}", why)),
+            failures: previous.map(|p| p.failures).unwrap_or(0) + 1,
+        },
+    };
+
+    diesel::insert_into(github_sync_cursor::table)
+        .values(&cursor)
+        .on_conflict(github_sync_cursor::repository)
+        .do_update()
+        .set(&cursor)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Repositories whose last sync failed, or which weren't synced successfully since `since`.
+pub fn stale_repos(since: NaiveDateTime) -> DashResult<Vec<GitHubSyncCursor>> {
+    let conn = &*DB_POOL.get()?;
+    Ok(github_sync_cursor::table
+        .filter(github_sync_cursor::successful.eq(false)
+            .or(github_sync_cursor::synced_until.lt(since)))
+        .order(github_sync_cursor::repository)
+        .load(conn)?)
+}
+
+/// The validators of the last response we got for a listing, if any.
+fn cached_validators(conn: &PgConnection, key: &str) -> DashResult<Option<Validators>> {
+    use domain::schema::github_etag::dsl::*;
+    let cached = github_etag.find(key).first::<GitHubEtag>(conn).optional()?;
+    Ok(cached.map(|c| Validators {
+        etag: c.etag,
+        last_modified: c.last_modified,
+    }))
+}
+
+/// Remember the validators of a listing's response, or forget them if it
+/// came without any.
+fn store_validators(conn: &PgConnection,
+                    key: &str,
+                    validators: Option<Validators>)
+                    -> DashResult<()> {
+    use domain::schema::github_etag::dsl::*;
+    match validators {
+        Some(validators) => {
+            let cached = GitHubEtag {
+                url: key.to_string(),
+                etag: validators.etag,
+                last_modified: validators.last_modified,
+                fetched_at: Utc::now().naive_utc(),
+            };
+            diesel::insert_into(github_etag)
+                .values(&cached)
+                .on_conflict(url)
+                .do_update()
+                .set(&cached)
+                .execute(conn)?;
+        }
+        None => {
+            diesel::delete(github_etag.find(key)).execute(conn)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn ingest_since(repo: &str, start: DateTime<Utc>) -> DashResult<()> {
     info!("fetching all {} issues and comments since {}", repo, start);
-    let issues = GH.issues_since(repo, start)?;
-    let mut comments = GH.comments_since(repo, start)?;
+    let conn = &*DB_POOL.get()?;
+
+    let issues_key = format!("/repos/{}/issues", repo);
+    let comments_key = format!("/repos/{}/issues/comments", repo);
+    let cached_issues = cached_validators(conn, &issues_key)?;
+    let cached_comments = cached_validators(conn, &comments_key)?;
+
+    // listings which didn't change since the last scrape were ingested already
+    let (issues, issues_validators) =
+        match GH.issues_since(repo, start, cached_issues.as_ref())? {
+            Fetched::Modified(issues, validators) => (issues, Some(validators)),
+            Fetched::NotModified => (vec![], None),
+        };
+    let (mut comments, comments_validators) =
+        match GH.comments_since(repo, start, cached_comments.as_ref())? {
+            Fetched::Modified(comments, validators) => (comments, Some(validators)),
+            Fetched::NotModified => (vec![], None),
+        };
+
     // make sure we process the new comments in creation order
     comments.sort_by_key(|c| c.created_at);
 
     let mut prs: Vec<PullRequestFromJson> = vec![];
     for issue in &issues {
-        // sleep(Duration::from_millis(github::client::DELAY));
         if let Some(ref pr_info) = issue.pull_request {
             prs.push(ok_or!(GH.fetch_pull_request(pr_info), why => {
                 error!("ERROR fetching PR info: {:?}", why);
@@ -86,30 +230,49 @@ pub fn ingest_since(repo: &str, start: DateTime<Utc>) -> DashResult<()> {
            &start,
            comments.len());
 
-    let conn = &*DB_POOL.get()?;
     debug!("let's insert some stuff in the database");
 
+    // only skip a listing next time if everything in it made it into the database
+    let mut issues_ingested = prs.len() == issues.iter().filter(|i| i.pull_request.is_some()).count();
+    let mut comments_ingested = true;
 
     // make sure we have all of the users to ensure referential integrity
     for issue in issues {
         let issue_number = issue.number;
-        ok_or!(handle_issue(conn, issue, repo), why =>
+        ok_or!(handle_issue(conn, issue, repo), why => {
             error!("Error processing issue {}#{}: {:?}",
-                   repo, issue_number, why));
+                   repo, issue_number, why);
+            issues_ingested = false;
+        });
     }
 
     // insert the comments
     for comment in comments {
         let comment_id = comment.id;
-        ok_or!(handle_comment(conn, comment, repo), why =>
+        ok_or!(handle_comment(conn, comment, repo), why => {
             error!("Error processing comment {}#{}: {:?}",
-                   repo, comment_id, why));
+                   repo, comment_id, why);
+            comments_ingested = false;
+        });
     }
 
     for pr in prs {
         let pr_number = pr.number;
-        ok_or!(handle_pr(conn, pr, repo), why =>
-            error!("Error processing PR {}#{}: {:?}", repo, pr_number, why));
+        ok_or!(handle_pr(conn, pr, repo), why => {
+            error!("Error processing PR {}#{}: {:?}", repo, pr_number, why);
+            issues_ingested = false;
+        });
+    }
+
+    if let Some(validators) = issues_validators {
+        store_validators(conn, &issues_key, if issues_ingested { validators } else { None })?;
+    }
+    if let Some(validators) = comments_validators {
+        store_validators(conn, &comments_key, if comments_ingested { validators } else { None })?;
+    }
+
+    if !issues_ingested || !comments_ingested {
+        throw!(DashError::Misc(Some(format!("some of {}'s changes couldn't be ingested", repo))));
     }
 
     Ok(())
@@ -138,15 +301,25 @@ pub fn handle_comment(conn: &PgConnection, comment: CommentFromJson, repo: &str)
 
     let comment: IssueComment = comment.with_repo(repo)?;
 
-    // We only want to run `nag::update_nags` on insert to avoid
-    // double-processing commits, so we can't use upsert here
-    if issuecomment::table
-           .find(comment.id)
-           .get_result::<IssueComment>(conn)
-           .is_ok() {
+    // We only want to run `nag::update_nags` on insert, and on edits only for
+    // the commands the edit added, to avoid double-processing commands, so we
+    // can't use upsert here
+    let previous = issuecomment::table
+        .find(comment.id)
+        .get_result::<IssueComment>(conn)
+        .optional()?;
+
+    if let Some(previous) = previous {
         diesel::update(issuecomment::table.find(comment.id))
             .set(&comment)
             .execute(conn)?;
+
+        if previous.body != comment.body {
+            ok_or!(nag::update_nags_for_edit(&previous, &comment), why => {
+                error!("Problem updating FCPs for edited comment: {:?}", &why);
+                throw!(why);
+            });
+        }
     } else {
         diesel::insert_into(issuecomment::table)
             .values(&comment)
@@ -161,6 +334,24 @@ pub fn handle_comment(conn: &PgConnection, comment: CommentFromJson, repo: &str)
     Ok(())
 }
 
+pub fn handle_deleted_comment(conn: &PgConnection, comment: CommentFromJson) -> DashResult<()> {
+    // We keep our own record of the comment, as proposals, concerns and
+    // feedback requests may still refer to it, but undo what it did.
+    let known = issuecomment::table
+        .find(comment.id)
+        .get_result::<IssueComment>(conn)
+        .optional()?;
+
+    if let Some(comment) = known {
+        ok_or!(nag::update_nags_for_deletion(&comment), why => {
+            error!("Problem updating FCPs for deleted comment: {:?}", &why);
+            throw!(why);
+        });
+    }
+
+    Ok(())
+}
+
 pub fn handle_issue(conn: &PgConnection, issue: IssueFromJson, repo: &str) -> DashResult<()> {
     // user handling
     handle_user(conn, &issue.user)?;
@@ -183,15 +374,33 @@ pub fn handle_issue(conn: &PgConnection, issue: IssueFromJson, repo: &str) -> Da
     }
 
     // handle issue itself
-    {
+    let (previous, updated) = {
         use domain::schema::issue::dsl::*;
 
-        diesel::insert_into(issue)
+        let previous = issue
+            .filter(repository.eq(&i.repository))
+            .filter(number.eq(i.number))
+            .first::<Issue>(conn)
+            .optional()?;
+
+        let updated = diesel::insert_into(issue)
             .values(&i)
             .on_conflict((repository, number))
             .do_update()
             .set(&i)
-            .execute(conn)?;
+            .get_result::<Issue>(conn)?;
+
+        (previous, updated)
+    };
+
+    // team labels may have been added or removed since we last saw the issue
+    if let Some(previous) = previous {
+        if previous.labels != updated.labels {
+            ok_or!(nag::update_nags_for_labels(&previous, &updated), why => {
+                error!("Problem updating FCPs for relabelled issue: {:?}", &why);
+                throw!(why);
+            });
+        }
     }
 
     Ok(())
@@ -211,6 +420,7 @@ pub fn handle_user(conn: &PgConnection, user: &GitHubUser) -> DashResult<()> {
 mod tests {
     use super::*;
     use std::env;
+    use chrono::Duration;
 
     #[test]
     fn test_handle_user() {
@@ -248,4 +458,61 @@ mod tests {
             .execute(&conn)
             .expect("Failed to clear database");
     }
+
+    #[test]
+    fn unchanged_listings_are_not_ingested_again() {
+        let h = Harness::new("rfcbot-test/etag");
+        let start = Utc::now() - Duration::minutes(1);
+        let skipped = GH.not_modified(h.repo);
+
+        // nothing happened since the harness scraped the repository
+        ingest_since(h.repo, start).unwrap();
+        assert_eq!(GH.not_modified(h.repo), skipped + 2);
+
+        // the bot's comment changes the comments, but not the issues
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        ingest_since(h.repo, start).unwrap();
+        assert_eq!(GH.not_modified(h.repo), skipped + 3);
+
+        ingest_since(h.repo, start).unwrap();
+        assert_eq!(GH.not_modified(h.repo), skipped + 5);
+    }
+
+    #[test]
+    fn repositories_are_synced_independently() {
+        let repo = "rfcbot-test/sync";
+        let forget = || diesel::delete(github_sync_cursor::table
+                .filter(github_sync_cursor::repository.eq(repo)))
+            .execute(&*DB_POOL.get().unwrap())
+            .unwrap();
+        forget();
+        let at = |hour| NaiveDate::from_ymd(2018, 8, 23).and_hms(hour, 0, 0);
+        let utc = |hour| DateTime::<Utc>::from_utc(at(hour), Utc);
+        let is_stale = |since| stale_repos(since).unwrap().iter().any(|c| c.repository == repo);
+
+        // never synced on its own
+        assert_eq!(sync_cursor(repo, utc(1)).unwrap(), utc(1));
+
+        record_sync(repo, at(2), &Ok(())).unwrap();
+        assert_eq!(sync_cursor(repo, utc(1)).unwrap(), utc(2));
+        assert!(!is_stale(at(1)));
+        assert!(is_stale(at(3)));
+
+        // a failure keeps the cursor where it was, to be retried next time
+        let failed = Err(DashError::Misc(Some("oops".to_string())));
+        record_sync(repo, at(3), &failed).unwrap();
+        record_sync(repo, at(4), &failed).unwrap();
+        assert_eq!(sync_cursor(repo, utc(1)).unwrap(), utc(2));
+        assert!(is_stale(at(1)));
+        let cursor = stale_repos(at(1)).unwrap().into_iter().find(|c| c.repository == repo).unwrap();
+        assert_eq!(cursor.failures, 2);
+        assert_eq!(cursor.last_attempt_at, at(4));
+        assert!(!cursor.successful && cursor.message.is_some());
+
+        record_sync(repo, at(5), &Ok(())).unwrap();
+        assert_eq!(sync_cursor(repo, utc(1)).unwrap(), utc(5));
+        assert!(!is_stale(at(4)));
+
+        forget();
+    }
 }
@@ -1,7 +1,8 @@
 use std::collections::BTreeSet;
 use std::sync::Mutex;
 
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel;
 
@@ -9,39 +10,103 @@ use itertools::Itertools;
 
 use DB_POOL;
 use domain::github::{GitHubUser, Issue, IssueComment};
-use domain::rfcbot::{FcpConcern, FcpProposal, FcpReviewRequest, FeedbackRequest, NewFcpProposal,
-                     NewFcpConcern, NewFcpReviewRequest, NewFeedbackRequest,
+use domain::rfcbot::{FcpConcern, FcpDispositionChange, FcpHold, FcpProposal, FcpReviewRequest,
+                     FeedbackRequest, NewFcpProposal, NewFcpConcern, NewFcpDispositionChange,
+                     NewFcpHold, NewFcpReviewRequest, ProposalStatus,
+                     NewFeedbackRequest, NewRfcbotEvent, EventKind,
                      NewPoll, Poll, NewPollResponseRequest, PollResponseRequest};
 use domain::schema::*;
 use error::*;
 use github::models::CommentFromJson;
-use teams::SETUP;
-use super::GH;
+use teams::{DispositionChangeReviews, RfcbotConfig, SETUP};
+use super::{GH, posting_enabled};
+use super::client::GitHubApi;
+use super::outbox::{self, Action};
 
 use github::command::*;
 
 impl Issue {
-    fn remove_label(&self, label: Label) {
-        let _ = GH.remove_label(&self.repository, self.number, label.as_str());
+    fn enqueue_add_label(&self, conn: &PgConnection, label: Label) -> DashResult<bool> {
+        self.enqueue(conn, Action::AddLabel(label.as_str()), Some(EventKind::LabelAdded))
     }
 
-    fn add_label(&self, label: Label) -> DashResult<()> {
-        GH.add_label(&self.repository, self.number, label.as_str())
+    fn enqueue_remove_label(&self, conn: &PgConnection, label: Label) -> DashResult<bool> {
+        self.enqueue(conn, Action::RemoveLabel(label.as_str()), Some(EventKind::LabelRemoved))
     }
 
-    fn close(&self) {
-        ok_or!(GH.close_issue(&self.repository, self.number), why =>
-            error!("Unable to close issue {:?}: {:?}", self, why));
+    fn enqueue_close(&self, conn: &PgConnection) -> DashResult<bool> {
+        self.enqueue(conn, Action::CloseIssue, Some(EventKind::IssueClosed))
     }
+
+    /// Queue a change to the issue on GitHub, and record `event` for it, using
+    /// `conn` so that it can be part of the transaction making the change it
+    /// follows from. Returns whether anything was queued, which is to be
+    /// delivered once the transaction has been committed.
+    fn enqueue(&self, conn: &PgConnection, action: Action, event: Option<EventKind>)
+        -> DashResult<bool>
+    {
+        if outbox::enqueue(conn, self, action)?.is_none() {
+            return Ok(false);
+        }
+
+        if let Some(kind) = event {
+            insert_event(conn, &NewRfcbotEvent {
+                detail: match action {
+                    Action::AddLabel(label) | Action::RemoveLabel(label) => label,
+                    _ => "",
+                },
+                ..NewRfcbotEvent::new(self.id, kind)
+            })?;
+        }
+
+        Ok(true)
+    }
+
+    /// Try to deliver the changes queued for the issue right away. If that
+    /// fails, they're retried in the background, so it isn't an error.
+    fn deliver(&self) {
+        ok_or!(outbox::deliver_for_issue(self.id), why =>
+            warn!("Unable to deliver the changes to {}#{} yet, will retry: {:?}",
+                  self.repository, self.number, why));
+    }
+}
+
+/// Append an event to the audit log. Failing to do so is logged, but doesn't
+/// stop us, as what the event records has happened already.
+fn record_event(event: NewRfcbotEvent) {
+    let conn = ok_or!(DB_POOL.get(), why => {
+        error!("Unable to record {} event for issue {}: {:?}", event.kind, event.fk_issue, why);
+        return;
+    });
+
+    ok_or!(insert_event(&*conn, &event), why =>
+        error!("Unable to record {} event for issue {}: {:?}", event.kind, event.fk_issue, why));
+}
+
+/// Append an event to the audit log using `conn`, as part of the transaction
+/// making the change it records.
+fn insert_event(conn: &PgConnection, event: &NewRfcbotEvent) -> DashResult<()> {
+    diesel::insert_into(rfcbot_event::table).values(event).execute(conn)?;
+    Ok(())
 }
 
 lazy_static! {
     static ref NAG_LOCK: Mutex<()> = Mutex::new(());
 }
 
-// TODO check if new subteam label added for existing proposals
+/// Reply about rejected commands at most this often per author and issue.
+const FEEDBACK_INTERVAL_MINS: i64 = 10;
 
 pub fn update_nags(comment: &IssueComment) -> DashResult<()> {
+    process_comment(comment, None)
+}
+
+/// Process the commands which were added to a comment by editing it.
+pub fn update_nags_for_edit(previous: &IssueComment, comment: &IssueComment) -> DashResult<()> {
+    process_comment(comment, Some(previous))
+}
+
+fn process_comment(comment: &IssueComment, previous: Option<&IssueComment>) -> DashResult<()> {
     let _in_progress_marker = NAG_LOCK.lock();
 
     let conn = &*DB_POOL.get()?;
@@ -52,52 +117,492 @@ pub fn update_nags(comment: &IssueComment) -> DashResult<()> {
         .find(comment.fk_user)
         .first::<GitHubUser>(conn)?;
 
-    let subteam_members = subteam_members(&issue)?;
     let all_team_members = all_team_members()?;
 
+    // the configuration may be reloaded while we're working, stick to this one
+    let setup = SETUP.get();
+
+    // Commands which were already in the comment before it was edited
+    // have been processed (or complained about) before, so we don't want
+    // to do that again.
+    let (previous_commands, previous_errors) = previous
+        .map(|p| parse_comment(&setup, &p.body))
+        .unwrap_or_default();
+
     // Attempt to parse all commands out of the comment
+    let (commands, errors) = parse_comment(&setup, &comment.body);
+
+    // what we tell the author about the commands we couldn't act on
+    let mut feedback = errors.into_iter()
+        .filter(|why| !previous_errors.contains(why))
+        .collect::<Vec<_>>();
+
     let mut any = false;
-    for command in RfcBotCommand::from_str_all(&SETUP, &comment.body) {
+    let mut outcomes = Vec::new();
+    for command in commands {
+        if previous_commands.iter().any(|p| *p == command) {
+            debug!("skipping already processed command: {:?}", &command);
+            continue;
+        }
+
         any = true;
 
-        if let RfcBotCommand::StartPoll {..} = command {
-            // Accept poll requests from any known user.
-            if all_team_members.iter().find(|&u| u == &author).is_none() {
-                info!("poll requester ({}) is not a known user", author.login);
-                return Ok(());
-            }
+        // a proposal made by an earlier command of the comment counts too
+        let teams = command_teams(&command, &issue)?;
+        let subteam_members = specific_subteam_members(|label| teams.contains(label))?;
+
+        let outcome = if !may_give_command(&command, &author, &subteam_members, &all_team_members) {
+            info!("command author ({}) isn't allowed to give {:?}", author.login, &command);
+            feedback.push(unauthorized_feedback(&teams, &command));
+            CommandOutcome::Unauthorized
         } else {
-            // Don't accept most bot commands from non-subteam members.
-            // Early return because we'll just get here again...
-            if subteam_members.iter().find(|&u| u == &author).is_none() {
-                info!("command author ({}) doesn't appear in any relevant subteams",
-                      author.login);
-                return Ok(());
+            debug!("processing rfcbot command: {:?}", &command);
+            match command.process(&author, &issue, comment, &subteam_members) {
+                Ok(()) => CommandOutcome::Processed,
+                Err(DashError::Misc(Some(why))) => {
+                    info!("Rejected command {:?} for comment id {}: {}",
+                          &command, comment.id, why);
+                    feedback.push(why.clone());
+                    CommandOutcome::Failed(DashError::Misc(Some(why)))
+                }
+                Err(why) => {
+                    error!("Unable to process command {:?} for comment id {}: {:?}",
+                           &command, comment.id, why);
+                    CommandOutcome::Failed(why)
+                }
             }
-        }
+        };
 
-        debug!("processing rfcbot command: {:?}", &command);
-        let process = command.process(&author, &issue, comment, &subteam_members);
-        ok_or!(process, why => {
-            error!("Unable to process command for comment id {}: {:?}",
-                comment.id, why);
-            return Ok(());
-        });
+        outcomes.push((command, outcome));
+    }
 
-        debug!("rfcbot command is processed");
+    if !outcomes.is_empty() {
+        log_command_outcomes(comment, &outcomes);
     }
 
-    if !any {
+    if !any && previous.is_none() {
         ok_or!(resolve_applicable_feedback_requests(&author, &issue, comment),
             why => error!("Unable to resolve feedback requests for comment id {}: {:?}",
                         comment.id, why));
     }
 
+    give_command_feedback(&issue, &author, &feedback);
+
+    evaluate_nags();
+
+    Ok(())
+}
+
+/// What became of a single command in a comment.
+#[derive(Debug)]
+enum CommandOutcome {
+    Processed,
+    Unauthorized,
+    Failed(DashError),
+}
+
+/// The labels of the teams whose members may give `command` on `issue`. The
+/// commands about a proposal are for the teams it was made to, the others
+/// for those tagged on the issue.
+fn command_teams(command: &RfcBotCommand, issue: &Issue) -> DashResult<BTreeSet<String>> {
+    match *command {
+        RfcBotCommand::FcpPropose(..) |
+        RfcBotCommand::FeedbackRequest(_) |
+        RfcBotCommand::StartPoll { .. } => Ok(labelled_teams(issue)),
+        _ => involved_teams(issue),
+    }
+}
+
+/// Polls can be started by any team member, everything else is reserved
+/// for members of the teams tagged on the issue, or the teams the proposal
+/// was made to.
+fn may_give_command(command: &RfcBotCommand,
+                    author: &GitHubUser,
+                    subteam_members: &[GitHubUser],
+                    all_team_members: &[GitHubUser])
+                    -> bool {
+    if let RfcBotCommand::StartPoll { .. } = *command {
+        all_team_members.contains(author)
+    } else {
+        subteam_members.contains(author)
+    }
+}
+
+fn log_command_outcomes(comment: &IssueComment, outcomes: &[(RfcBotCommand, CommandOutcome)]) {
+    let (mut processed, mut unauthorized, mut failed) = (0, 0, 0);
+    for &(_, ref outcome) in outcomes {
+        match *outcome {
+            CommandOutcome::Processed => processed += 1,
+            CommandOutcome::Unauthorized => unauthorized += 1,
+            CommandOutcome::Failed(_) => failed += 1,
+        }
+    }
+
+    let summary = format!("comment id {}: {} command(s) processed, {} unauthorized, {} failed",
+                          comment.id, processed, unauthorized, failed);
+    if failed > 0 {
+        warn!("{}", summary);
+    } else {
+        info!("{}", summary);
+    }
+
+    for &(ref command, ref outcome) in outcomes {
+        debug!("{:?} -> {:?}", command, outcome);
+    }
+}
+
+/// Parse the commands in a comment, along with the errors for the commands
+/// which we can't parse and which the author should hear about.
+fn parse_comment<'a>(setup: &'a RfcbotConfig, body: &'a str)
+    -> (Vec<RfcBotCommand<'a>>, Vec<String>)
+{
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+
+    for parsed in RfcBotCommand::parse_all(setup, body) {
+        match parsed {
+            Ok(command) => commands.push(command),
+            Err(DashError::Misc(Some(why))) => errors.push(why),
+            Err(why) => debug!("ignoring bot mention: {:?}", why),
+        }
+    }
+
+    (commands, errors)
+}
+
+/// Explain who may give a command which its author isn't allowed to give.
+fn unauthorized_feedback(teams: &BTreeSet<String>, command: &RfcBotCommand) -> String {
+    if let RfcBotCommand::StartPoll { .. } = *command {
+        return format!("`{}` can only be used by team members", command.keyword());
+    }
+
+    if teams.is_empty() {
+        format!("`{}` can only be used by members of the teams tagged on this issue, \
+                 and no team is tagged yet", command.keyword())
+    } else {
+        format!("`{}` can only be used by members of {}",
+                command.keyword(),
+                teams.iter().map(|team| format!("`{}`", team)).join(", "))
+    }
+}
+
+/// Reply to the author of a comment with the commands we couldn't act on,
+/// unless we've done so recently or the repository doesn't want us to.
+fn give_command_feedback(issue: &Issue, author: &GitHubUser, feedback: &[String]) {
+    if feedback.is_empty() {
+        return;
+    }
+
+    if !SETUP.get().should_give_command_feedback(&issue.repository) {
+        info!("not replying to {} on {}#{}, command feedback is suppressed",
+              author.login, issue.repository, issue.number);
+        return;
+    }
+
+    // the replies are recorded in the audit log, which tells us when we last replied
+    let since = Utc::now().naive_utc() - Duration::minutes(FEEDBACK_INTERVAL_MINS);
+    let replied = DB_POOL.get().map_err(DashError::from).and_then(|conn| {
+        Ok(rfcbot_event::table
+            .filter(rfcbot_event::fk_issue.eq(issue.id))
+            .filter(rfcbot_event::fk_actor.eq(author.id))
+            .filter(rfcbot_event::kind.eq(EventKind::CommandFeedback.repr()))
+            .filter(rfcbot_event::created_at.gt(since))
+            .count()
+            .get_result::<i64>(&*conn)? > 0)
+    });
+    match replied {
+        Ok(false) => (),
+        Ok(true) => {
+            info!("not replying to {} on {}#{} again so soon",
+                  author.login, issue.repository, issue.number);
+            return;
+        }
+        Err(why) => {
+            error!("Unable to find out when we last replied to {} on {}#{}: {:?}",
+                   author.login, issue.repository, issue.number, why);
+            return;
+        }
+    }
+
+    // record the reply along with queueing it, so we can't reply again
+    // without having told the author, or the other way around
+    let detail = feedback.join("\n");
+    let comment = RfcBotComment::new(issue, CommentType::CommandFeedback {
+        author,
+        feedback,
+    });
+    let queued = DB_POOL.get().map_err(DashError::from).and_then(|conn| {
+        conn.transaction::<_, DashError, _>(|| {
+            insert_event(&*conn, &NewRfcbotEvent {
+                fk_actor: Some(author.id),
+                detail: &detail,
+                ..NewRfcbotEvent::new(issue.id, EventKind::CommandFeedback)
+            })?;
+            comment.enqueue(&*conn, None, false)
+        })
+    });
+    ok_or!(queued, why => {
+        warn!("Unable to reply to {} on {}#{}: {:?}",
+              author.login, issue.repository, issue.number, why);
+        return;
+    });
+
+    issue.deliver();
+}
+
+/// Undo, as far as possible, what the commands in a now deleted comment did:
+///
+/// * a proposal made by the comment is cancelled, unless its FCP has finished,
+/// * concerns raised by the comment are dropped,
+/// * concerns resolved by the comment are open again,
+/// * a hold placed by the comment is released,
+/// * feedback given by the comment is no longer counted.
+pub fn update_nags_for_deletion(comment: &IssueComment) -> DashResult<()> {
+    let _in_progress_marker = NAG_LOCK.lock();
+
+    let conn = &*DB_POOL.get()?;
+
+    let issue = issue::table.find(comment.fk_issue).first::<Issue>(conn)?;
+
+    let author = githubuser::table
+        .find(comment.fk_user)
+        .first::<GitHubUser>(conn)?;
+
+    let proposal = fcp_proposal::table
+        .filter(fcp_proposal::fk_initiating_comment.eq(comment.id))
+        .first::<FcpProposal>(conn)
+        .optional()?;
+
+    if let Some(proposal) = proposal {
+        if proposal.is_active() {
+            info!("initiating comment {} of proposal {} was deleted, cancelling",
+                  comment.id, proposal.id);
+            cancel_fcp(conn, &author, &issue, &proposal)?;
+        }
+    }
+
+    let dropped = diesel::delete(fcp_concern::table
+            .filter(fcp_concern::fk_initiating_comment.eq(comment.id)))
+        .get_results::<FcpConcern>(conn)?;
+    for concern in &dropped {
+        info!("dropped concern {} raised by deleted comment {}", concern.name, comment.id);
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_proposal: Some(concern.fk_proposal),
+            fk_comment: Some(comment.id),
+            detail: &concern.name,
+            ..NewRfcbotEvent::new(issue.id, EventKind::ConcernDropped)
+        });
+    }
+
+    let reopened = diesel::update(fcp_concern::table
+            .filter(fcp_concern::fk_resolved_comment.eq(comment.id)))
+        .set(fcp_concern::fk_resolved_comment.eq(None::<i32>))
+        .get_results::<FcpConcern>(conn)?;
+    for concern in &reopened {
+        info!("reopened concern {} resolved by deleted comment {}", concern.name, comment.id);
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_proposal: Some(concern.fk_proposal),
+            fk_comment: Some(comment.id),
+            detail: &concern.name,
+            ..NewRfcbotEvent::new(issue.id, EventKind::ConcernReopened)
+        });
+    }
+    if !reopened.is_empty() {
+        // an open concern takes us out of FCP and back into PFCP if need be
+        if let Some(mut proposal) = existing_proposal(&issue)? {
+            if !proposal.fcp_closed {
+                conn.transaction::<_, DashError, _>(|| back_to_pfcp(conn, &issue, &mut proposal))?;
+                issue.deliver();
+            }
+        }
+    }
+
+    let hold = fcp_hold::table
+        .filter(fcp_hold::fk_initiating_comment.eq(comment.id))
+        .filter(fcp_hold::released_at.is_null())
+        .first::<FcpHold>(conn)
+        .optional()?;
+    if let Some(mut hold) = hold {
+        info!("releasing hold {} placed by deleted comment {}", hold.id, comment.id);
+        let mut proposal = fcp_proposal::table.find(hold.fk_proposal).first::<FcpProposal>(conn)?;
+        release_hold(conn, &mut proposal, &mut hold, None)?;
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_proposal: Some(proposal.id),
+            fk_comment: Some(comment.id),
+            ..NewRfcbotEvent::new(issue.id, EventKind::HoldReleased)
+        });
+    }
+
+    diesel::update(rfc_feedback_request::table
+            .filter(rfc_feedback_request::fk_feedback_comment.eq(comment.id)))
+        .set(rfc_feedback_request::fk_feedback_comment.eq(None::<i32>))
+        .execute(conn)?;
+
+    evaluate_nags();
+
+    Ok(())
+}
+
+/// Bring the review requests of a pending proposal, and the response requests
+/// of an open poll, in line with the teams labelled on the issue now.
+pub fn update_nags_for_labels(previous: &Issue, issue: &Issue) -> DashResult<()> {
+    let before = labelled_teams(previous);
+    let after = labelled_teams(issue);
+    if before == after {
+        return Ok(());
+    }
+
+    let _in_progress_marker = NAG_LOCK.lock();
+
+    info!("teams of {}#{} changed from {:?} to {:?}",
+          issue.repository, issue.number, before, after);
+
+    let conn = &*DB_POOL.get()?;
+
+    // once the FCP has started, the reviewers are set in stone, and proposals
+    // made to the teams named in the command are for those teams only
+    if let Some(mut proposal) = existing_proposal(issue)? {
+        if proposal.fcp_start.is_none() && !proposal.fcp_closed && !proposal.fcp_teams_scoped {
+            proposal.fcp_teams = after.iter().map(|team| &**team).intersperse(",").collect();
+            diesel::update(fcp_proposal::table.find(proposal.id))
+                .set(&proposal)
+                .execute(conn)?;
+
+            let members = specific_subteam_members(|label| after.contains(label))?;
+            update_review_requests(&proposal, &members)?;
+        }
+    }
+
+    let polls = poll::table
+        .filter(poll::fk_issue.eq(issue.id))
+        .filter(poll::poll_closed.eq(false))
+        .load::<Poll>(conn)?;
+
+    for mut survey in polls {
+        let teams = retarget_teams(survey.poll_teams.split(',').filter(|team| !team.is_empty()),
+                                   &before, &after);
+
+        survey.poll_teams = teams.iter().map(|team| &**team).intersperse(",").collect();
+        diesel::update(poll::table.find(survey.id))
+            .set(&survey)
+            .execute(conn)?;
+
+        let members = specific_subteam_members(|label| teams.contains(label))?;
+        update_response_requests(&survey, &members)?;
+    }
+
+    // re-render the tracking comments
     evaluate_nags();
 
     Ok(())
 }
 
+/// Polls may involve teams which aren't labelled, or not all of those which
+/// are, so only add and remove the teams whose labels changed.
+fn retarget_teams<'a, I>(teams: I, before: &BTreeSet<String>, after: &BTreeSet<String>)
+    -> BTreeSet<String>
+    where I: Iterator<Item = &'a str>
+{
+    let mut teams = teams.map(String::from).collect::<BTreeSet<_>>();
+    teams.extend(after.difference(before).cloned());
+    for removed in before.difference(after) {
+        teams.remove(removed);
+    }
+    teams
+}
+
+/// The labels of the teams a proposal on `issue` was made to.
+fn proposal_teams(proposal: &FcpProposal, issue: &Issue) -> BTreeSet<String> {
+    proposal.teams(&labelled_teams(issue))
+}
+
+/// The labels of the teams the issue's current proposal was made to, or of
+/// those tagged on the issue if it isn't going through one.
+fn involved_teams(issue: &Issue) -> DashResult<BTreeSet<String>> {
+    Ok(match existing_proposal(issue)? {
+        Some(proposal) => proposal_teams(&proposal, issue),
+        None => labelled_teams(issue),
+    })
+}
+
+/// The labels of the teams which are labelled on the issue.
+fn labelled_teams(issue: &Issue) -> BTreeSet<String> {
+    SETUP.get()
+         .team_labels()
+         .filter(|label| issue.labels.contains(&label.0))
+         .map(|label| label.0.clone())
+         .collect()
+}
+
+/// Request reviews from new members, and drop the requests of those who are
+/// no longer involved. Existing reviews are kept.
+fn update_review_requests(proposal: &FcpProposal, members: &[GitHubUser]) -> DashResult<()> {
+    use domain::schema::fcp_review_request::dsl::*;
+    let conn = &*DB_POOL.get()?;
+
+    let existing = fcp_review_request
+        .filter(fk_proposal.eq(proposal.id))
+        .load::<FcpReviewRequest>(conn)?;
+
+    let new_requests = members
+        .iter()
+        .filter(|member| !existing.iter().any(|r| r.fk_reviewer == member.id))
+        .map(|member| NewFcpReviewRequest {
+            fk_proposal: proposal.id,
+            fk_reviewer: member.id,
+            reviewed: false,
+        })
+        .collect::<Vec<_>>();
+
+    if !new_requests.is_empty() {
+        diesel::insert_into(fcp_review_request)
+            .values(&new_requests)
+            .execute(conn)?;
+    }
+
+    for request in existing.iter().filter(|r| !members.iter().any(|m| m.id == r.fk_reviewer)) {
+        diesel::delete(fcp_review_request.find(request.id)).execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Ask new members to respond to a poll, and drop the requests of those who
+/// are no longer involved. Existing responses are kept.
+fn update_response_requests(survey: &Poll, members: &[GitHubUser]) -> DashResult<()> {
+    use domain::schema::poll_response_request::dsl::*;
+    let conn = &*DB_POOL.get()?;
+
+    let existing = poll_response_request
+        .filter(fk_poll.eq(survey.id))
+        .load::<PollResponseRequest>(conn)?;
+
+    let new_requests = members
+        .iter()
+        .filter(|member| !existing.iter().any(|r| r.fk_respondent == member.id))
+        .map(|member| NewPollResponseRequest {
+            fk_poll: survey.id,
+            fk_respondent: member.id,
+            responded: false,
+        })
+        .collect::<Vec<_>>();
+
+    if !new_requests.is_empty() {
+        diesel::insert_into(poll_response_request)
+            .values(&new_requests)
+            .execute(conn)?;
+    }
+
+    for request in existing.iter().filter(|r| !members.iter().any(|m| m.id == r.fk_respondent)) {
+        diesel::delete(poll_response_request.find(request.id)).execute(conn)?;
+    }
+
+    Ok(())
+}
+
 fn update_proposal_review_status(proposal_id: i32) -> DashResult<()> {
     let conn = &*DB_POOL.get()?;
     // this is an updated comment from the bot itself
@@ -107,7 +612,7 @@ fn update_proposal_review_status(proposal_id: i32) -> DashResult<()> {
     let proposal: FcpProposal = fcp_proposal::table.find(proposal_id).first(conn)?;
 
     // don't update any statuses if the fcp is running or closed
-    if proposal.fcp_start.is_some() || proposal.fcp_closed {
+    if proposal.status != ProposalStatus::Proposed.repr() {
         return Ok(());
     }
 
@@ -128,10 +633,19 @@ fn update_proposal_review_status(proposal_id: i32) -> DashResult<()> {
                 .filter(fk_reviewer.eq(user.id))
                 .first(conn)?;
 
-            review_request.reviewed = true;
-            diesel::update(fcp_review_request.find(review_request.id))
-                .set(&review_request)
-                .execute(conn)?;
+            if !review_request.reviewed {
+                review_request.reviewed = true;
+                diesel::update(fcp_review_request.find(review_request.id))
+                    .set(&review_request)
+                    .execute(conn)?;
+
+                record_event(NewRfcbotEvent {
+                    fk_actor: Some(user.id),
+                    fk_proposal: Some(proposal.id),
+                    fk_comment: Some(comment.id),
+                    ..NewRfcbotEvent::new(proposal.fk_issue, EventKind::Reviewed)
+                });
+            }
         }
     }
 
@@ -248,7 +762,7 @@ fn evaluate_polls() -> DashResult<()> {
                     survey.id, why));
 
         // get associated responses
-        let responses = ok_or_continue!(list_poll_response_requests(survey.id), why =>
+        let responses = ok_or_continue!(list_poll_response_requests(conn, survey.id), why =>
             error!("Unable to retrieve response requests for survey {}: {:?}",
                     survey.id, why));
 
@@ -295,7 +809,8 @@ fn evaluate_pendings() -> DashResult<()> {
     let conn = &*DB_POOL.get()?;
 
     // first process all "pending" proposals (unreviewed or remaining concerns)
-    let pending = fcp_proposal.filter(fcp_start.is_null()).load::<FcpProposal>(conn);
+    let pending = fcp_proposal.filter(status.eq(ProposalStatus::Proposed.repr()))
+                              .load::<FcpProposal>(conn);
     let pending_proposals = ok_or!(pending, why => {
         error!("Unable to retrieve list of pending proposals: {:?}", why);
         throw!(why)
@@ -316,9 +831,10 @@ fn evaluate_pendings() -> DashResult<()> {
         // if the issue has been closed before an FCP starts,
         // then we just need to cancel the FCP entirely
         if !issue.open {
-            ok_or_continue!(cancel_fcp(&initiator, &issue, &proposal), why =>
+            ok_or_continue!(cancel_fcp(conn, &initiator, &issue, &proposal), why =>
                 error!("Unable to cancel FCP for proposal {}: {:?}",
                         proposal.id, why));
+            continue;
         }
 
         // check to see if any checkboxes were modified before we end up replacing the comment
@@ -327,27 +843,39 @@ fn evaluate_pendings() -> DashResult<()> {
                     proposal.id, why));
 
         // get associated concerns and reviews
-        let reviews = ok_or_continue!(list_review_requests(proposal.id), why =>
+        let reviews = ok_or_continue!(list_review_requests(conn, proposal.id), why =>
             error!("Unable to retrieve review requests for proposal {}: {:?}",
                     proposal.id, why));
 
-        let concerns = ok_or_continue!(list_concerns_with_authors(proposal.id),
+        let concerns = ok_or_continue!(list_concerns_with_authors(conn, proposal.id),
             why => error!("Unable to retrieve concerns for proposal {}: {:?}",
                     proposal.id, why));
 
+        let holds = ok_or_continue!(list_active_holds_with_authors(conn, proposal.id),
+            why => error!("Unable to retrieve holds for proposal {}: {:?}",
+                    proposal.id, why));
+
+        let changes = ok_or_continue!(list_disposition_changes_with_authors(conn, proposal.id),
+            why => error!("Unable to retrieve disposition changes for proposal {}: {:?}",
+                    proposal.id, why));
+
         let num_outstanding_reviews = reviews.iter().filter(|&&(_, ref r)| !r.reviewed).count();
         let num_complete_reviews = reviews.len() - num_outstanding_reviews;
         let num_active_concerns = concerns
             .iter()
-            .filter(|&&(_, ref c)| c.fk_resolved_comment.is_none())
+            .filter(|&&(_, ref c, _, _)| c.fk_resolved_comment.is_none())
             .count();
 
         // update existing status comment with reviews & concerns
+        let teams = proposal_teams(&proposal, &issue);
         let status_comment = RfcBotComment::new(&issue, CommentType::FcpProposed(
                     &initiator,
                     FcpDisposition::from_str(&proposal.disposition)?,
+                    teams.iter().map(|team| &**team).collect(),
+                    &changes,
                     &reviews,
-                    &concerns));
+                    &concerns,
+                    &holds));
 
         let previous_comment: IssueComment = issuecomment
             .filter(issuecomment_id.eq(proposal.fk_bot_tracking_comment))
@@ -357,55 +885,49 @@ fn evaluate_pendings() -> DashResult<()> {
             // if the comment body in the database equals the new one we generated, then no change
             // is needed from github (this assumes our DB accurately reflects GH's, which should
             // be true in most cases by the time this is called)
-            let post = status_comment.post(Some(proposal.fk_bot_tracking_comment));
+            let post = conn.transaction::<_, DashError, _>(||
+                status_comment.enqueue(conn, Some(proposal.fk_bot_tracking_comment), true));
             ok_or_continue!(post, why =>
                 error!("Unable to update status comment for proposal {}: {:?}",
                         proposal.id, why));
+            issue.deliver();
         }
 
         let majority_complete = num_outstanding_reviews < num_complete_reviews;
 
-        if num_active_concerns == 0 && majority_complete && num_outstanding_reviews < 3 {
-            // TODO only record the fcp as started if we know that we successfully commented
-            // i.e. either the comment claims to have posted, or we get a comment back to reconcile
-
-            // FCP can start now -- update the database
-            proposal.fcp_start = Some(Utc::now().naive_utc());
-            let update = diesel::update(fcp_proposal.find(proposal.id))
-                          .set(&proposal).execute(conn);
-            ok_or_continue!(update, why =>
-                error!("Unable to mark FCP {} as started: {:?}",
-                       proposal.id, why));
+        if num_active_concerns == 0 && holds.is_empty() &&
+           majority_complete && num_outstanding_reviews < 3 {
+            // FCP can start now -- update the database, and queue the label changes
+            // and the comment announcing it along with that, so they can't get lost
+            let now = Utc::now().naive_utc();
+            proposal.fcp_start = Some(now);
+            proposal.status = ProposalStatus::InFcp.repr().to_string();
 
-            // attempt to add the final-comment-period label
             // TODO only add label if FCP > 1 day
-            use config::CONFIG;
-            if CONFIG.post_comments {
-                let label_res = issue.add_label(Label::FCP);
-                issue.remove_label(Label::PFCP);
-                let added_label = match label_res {
-                    Ok(()) => true,
-                    Err(why) => {
-                        warn!("Unable to add FCP label to {}#{}: {:?}",
-                              &issue.repository,
-                              issue.number,
-                              why);
-                        false
-                    }
-                };
-
-                let comment_type = CommentType::FcpAllReviewedNoConcerns {
-                    added_label: added_label,
-                    author: &initiator,
+            let disp = FcpDisposition::from_str(&proposal.disposition)?;
+            let fcp_start_comment = RfcBotComment::new(&issue,
+                CommentType::FcpAllReviewedNoConcerns {
                     status_comment_id: proposal.fk_bot_tracking_comment,
-                };
+                    fcp_end: fcp_end(&issue, disp, now),
+                });
 
-                // leave a comment for FCP start
-                let fcp_start_comment = RfcBotComment::new(&issue, comment_type);
-                ok_or_continue!(fcp_start_comment.post(None), why =>
-                    error!("Unable to post comment for FCP {}'s start: {:?}",
-                            proposal.id, why));
-            }
+            let started = conn.transaction::<_, DashError, _>(|| {
+                diesel::update(fcp_proposal.find(proposal.id))
+                    .set(&proposal)
+                    .execute(conn)?;
+                insert_event(conn, &NewRfcbotEvent {
+                    fk_proposal: Some(proposal.id),
+                    ..NewRfcbotEvent::new(issue.id, EventKind::FcpStarted)
+                })?;
+                enqueue_label_swap(conn, &issue, Label::FCP, Label::PFCP)?;
+                fcp_start_comment.enqueue(conn, None, false)?;
+                Ok(())
+            });
+            ok_or_continue!(started, why =>
+                error!("Unable to mark FCP {} as started: {:?}",
+                       proposal.id, why));
+
+            issue.deliver();
         }
     }
 
@@ -417,109 +939,133 @@ fn evaluate_ffcps() -> DashResult<()> {
     use domain::schema::fcp_proposal::dsl::*;
     let conn = &*DB_POOL.get()?;
 
-    // look for any FCP proposals that entered FCP a week or more ago but aren't marked as closed
-    let one_business_week_ago = Utc::now().naive_utc() - Duration::days(10);
-    let ffcps = fcp_proposal.filter(fcp_start.le(one_business_week_ago))
-                            .filter(fcp_closed.eq(false))
+    // look for any FCP proposals that entered FCP but aren't marked as closed,
+    // we'll check below which of them have run for their configured duration
+    let now = Utc::now().naive_utc();
+    let ffcps = fcp_proposal.filter(status.eq(ProposalStatus::InFcp.repr()))
                             .load::<FcpProposal>(conn);
-    let finished_fcps = ok_or!(ffcps, why => {
+    let running_fcps = ok_or!(ffcps, why => {
         error!("Unable to retrieve FCPs that need to be marked as finished: {:?}",
                why);
         throw!(why);
     });
 
-    for mut proposal in finished_fcps {
-        let initiator = githubuser::table.find(proposal.fk_initiator)
-                                         .first::<GitHubUser>(conn);
-        let initiator = ok_or_continue!(initiator, why =>
-            error!("Unable to retrieve proposal initiator for proposal id {}: {:?}",
-                    proposal.id,
-                    why));
-
+    for mut proposal in running_fcps {
         let issue = issue::table.find(proposal.fk_issue).first::<Issue>(conn);
         let issue = ok_or_continue!(issue, why =>
             error!("Unable to find issue to match proposal {}: {:?}",
                    proposal.id, why));
 
-        // TODO only update the db if the comment posts, but reconcile if we find out it worked
-
-        // update the fcp
-        proposal.fcp_closed = true;
-        let update_fcp = diesel::update(fcp_proposal.find(proposal.id))
-                                .set(&proposal).execute(conn);
-        ok_or_continue!(update_fcp, why =>
-            error!("Unable to update FCP {}: {:?}", proposal.id, why));
-
         // parse the disposition:
         let disp = FcpDisposition::from_str(&proposal.disposition)?;
 
-        // Add FFCP label and remove FCP label.
-        let label_res = issue.add_label(Label::FFCP);
-        issue.remove_label(Label::FCP);
-        let added_label = match label_res {
-            Ok(_) => true,
-            Err(why) => {
-                warn!("Unable to add Finished-FCP label to {}#{}: {:?}",
-                        &issue.repository,
-                        issue.number,
-                        why);
-                false
-            }
+        // has this FCP run its course yet?
+        let started = match proposal.fcp_start {
+            Some(started) => started,
+            None => continue,
         };
+        if fcp_end(&issue, disp, started) > now {
+            continue;
+        }
+
+        // the clock is stopped while the proposal is on hold
+        let hold = ok_or_continue!(active_hold(proposal.id), why =>
+            error!("Unable to retrieve hold for proposal {}: {:?}",
+                   proposal.id, why));
+        if hold.is_some() {
+            debug!("FCP {} has run its course but is on hold", proposal.id);
+            continue;
+        }
+
+        // update the fcp, and queue the label changes, the comment announcing
+        // the outcome and what follows from it along with that, so they
+        // can't get lost
+        proposal.fcp_closed = true;
+        proposal.status = ProposalStatus::Finished.repr().to_string();
+        proposal.finished_at = Some(now);
 
-        // Build the comment:
-        let comment_type = CommentType::FcpWeekPassed {
-            added_label,
-            author: &initiator,
+        let fcp_close_comment = RfcBotComment::new(&issue, CommentType::FcpPassed {
             status_comment_id: proposal.fk_bot_tracking_comment,
             disposition: disp
-        };
-        let fcp_close_comment = RfcBotComment::new(&issue, comment_type);
+        });
 
-        // Post it!
-        ok_or_continue!(fcp_close_comment.post(None), why =>
-            error!("Unable to post FCP-ending comment for proposal {}: {:?}",
-                    proposal.id, why));
+        let finished = conn.transaction::<_, DashError, _>(|| {
+            diesel::update(fcp_proposal.find(proposal.id))
+                .set(&proposal)
+                .execute(conn)?;
+            insert_event(conn, &NewRfcbotEvent {
+                fk_proposal: Some(proposal.id),
+                detail: disp.repr(),
+                ..NewRfcbotEvent::new(issue.id, EventKind::FcpFinished)
+            })?;
+            enqueue_label_swap(conn, &issue, Label::FFCP, Label::FCP)?;
+            fcp_close_comment.enqueue(conn, None, false)?;
+            execute_ffcp_actions(conn, &issue, disp)
+        });
+        ok_or_continue!(finished, why =>
+            error!("Unable to update FCP {}: {:?}", proposal.id, why));
 
-        execute_ffcp_actions(&issue, disp);
+        issue.deliver();
     }
 
     Ok(())
 }
 
+/// Queue replacing one label of an issue with another, using `conn`, which is
+/// in a transaction.
+fn enqueue_label_swap(conn: &PgConnection, issue: &Issue, add: Label, remove: Label)
+    -> DashResult<()>
+{
+    if issue.enqueue_add_label(conn, add)? {
+        issue.enqueue_remove_label(conn, remove)?;
+    }
+    Ok(())
+}
+
+/// When does a final comment period which started at `start` end?
+fn fcp_end(issue: &Issue, disposition: FcpDisposition, start: NaiveDateTime) -> NaiveDateTime {
+    start + SETUP.get().fcp_duration(&issue.repository, disposition)
+}
+
 fn can_ffcp_close(issue: &Issue) -> bool {
-    SETUP.should_ffcp_auto_close(&issue.repository)
+    SETUP.get().should_ffcp_auto_close(&issue.repository)
 }
 
 fn can_ffcp_postpone(issue: &Issue) -> bool {
-    SETUP.should_ffcp_auto_postpone(&issue.repository)
+    SETUP.get().should_ffcp_auto_postpone(&issue.repository)
 }
 
-fn execute_ffcp_actions(issue: &Issue, disposition: FcpDisposition) {
+/// Queue what follows from an FCP finishing with `disposition`, using `conn`,
+/// which is in the transaction marking it as finished.
+fn execute_ffcp_actions(conn: &PgConnection, issue: &Issue, disposition: FcpDisposition)
+    -> DashResult<()>
+{
     match disposition {
         FcpDisposition::Merge => {
             // TODO: This one will require a lot of work to
             // auto-merge RFCs and create the tracking issue.
         },
         FcpDisposition::Close if can_ffcp_close(issue) => {
-            let _ = issue.add_label(Label::Closed);
-            issue.remove_label(Label::DispositionClose);
-            issue.close();
+            issue.enqueue_add_label(conn, Label::Closed)?;
+            issue.enqueue_remove_label(conn, Label::DispositionClose)?;
+            issue.enqueue_close(conn)?;
         },
         FcpDisposition::Postpone if can_ffcp_postpone(issue) => {
-            let _ = issue.add_label(Label::Postponed);
-            issue.remove_label(Label::DispositionPostpone);
-            issue.close();
+            issue.enqueue_add_label(conn, Label::Postponed)?;
+            issue.enqueue_remove_label(conn, Label::DispositionPostpone)?;
+            issue.enqueue_close(conn)?;
         },
         _ => {},
     }
+
+    Ok(())
 }
 
-fn list_review_requests(proposal_id: i32) -> DashResult<Vec<(GitHubUser, FcpReviewRequest)>> {
+fn list_review_requests(conn: &PgConnection, proposal_id: i32)
+    -> DashResult<Vec<(GitHubUser, FcpReviewRequest)>>
+{
     use domain::schema::{fcp_review_request, githubuser};
 
-    let conn = &*DB_POOL.get()?;
-
     let reviews = fcp_review_request::table
         .filter(fcp_review_request::fk_proposal.eq(proposal_id))
         .load::<FcpReviewRequest>(conn)?;
@@ -539,13 +1085,11 @@ fn list_review_requests(proposal_id: i32) -> DashResult<Vec<(GitHubUser, FcpRevi
     Ok(w_reviewers)
 }
 
-fn list_poll_response_requests(poll_id: i32)
+fn list_poll_response_requests(conn: &PgConnection, poll_id: i32)
     -> DashResult<Vec<(GitHubUser, PollResponseRequest)>>
 {
     use domain::schema::{poll_response_request, githubuser};
 
-    let conn = &*DB_POOL.get()?;
-
     let reviews = poll_response_request::table
         .filter(poll_response_request::fk_poll.eq(poll_id))
         .load::<PollResponseRequest>(conn)?;
@@ -565,10 +1109,13 @@ fn list_poll_response_requests(poll_id: i32)
     Ok(w_reviewers)
 }
 
-fn list_concerns_with_authors(proposal_id: i32) -> DashResult<Vec<(GitHubUser, FcpConcern)>> {
-    use domain::schema::{fcp_concern, githubuser};
-
-    let conn = &*DB_POOL.get()?;
+/// The concerns raised on a proposal, with their current owner, who resolved
+/// them if that wasn't the owner, and who raised them if they were
+/// transferred since.
+fn list_concerns_with_authors(conn: &PgConnection, proposal_id: i32)
+    -> DashResult<Vec<(GitHubUser, FcpConcern, Option<GitHubUser>, Option<GitHubUser>)>>
+{
+    use domain::schema::{fcp_concern, githubuser, issuecomment};
 
     let concerns = fcp_concern::table
         .filter(fcp_concern::fk_proposal.eq(proposal_id))
@@ -582,13 +1129,96 @@ fn list_concerns_with_authors(proposal_id: i32) -> DashResult<Vec<(GitHubUser, F
             .filter(githubuser::id.eq(concern.fk_initiator))
             .first::<GitHubUser>(conn)?;
 
-        w_authors.push((initiator, concern));
-    }
-
-    Ok(w_authors)
-}
+        let resolver = match concern.fk_resolved_comment {
+            Some(resolved_comment) => {
+                let resolved_by = issuecomment::table
+                    .find(resolved_comment)
+                    .select(issuecomment::fk_user)
+                    .first::<i32>(conn)?;
 
-fn resolve_applicable_feedback_requests(author: &GitHubUser,
+                if resolved_by == initiator.id {
+                    None
+                } else {
+                    Some(githubuser::table.find(resolved_by).first::<GitHubUser>(conn)?)
+                }
+            }
+            None => None,
+        };
+
+        let raised_by = issuecomment::table
+            .find(concern.fk_initiating_comment)
+            .select(issuecomment::fk_user)
+            .first::<i32>(conn)
+            .optional()?;
+        let raiser = match raised_by {
+            Some(raised_by) if raised_by != initiator.id =>
+                Some(githubuser::table.find(raised_by).first::<GitHubUser>(conn)?),
+            _ => None,
+        };
+
+        w_authors.push((initiator, concern, resolver, raiser));
+    }
+
+    Ok(w_authors)
+}
+
+fn list_active_holds_with_authors(conn: &PgConnection, proposal_id: i32)
+    -> DashResult<Vec<(GitHubUser, FcpHold)>>
+{
+    use domain::schema::{fcp_hold, githubuser};
+
+    let holds = fcp_hold::table
+        .filter(fcp_hold::fk_proposal.eq(proposal_id))
+        .filter(fcp_hold::released_at.is_null())
+        .order(fcp_hold::held_at)
+        .load::<FcpHold>(conn)?;
+
+    let mut w_authors = Vec::with_capacity(holds.len());
+
+    for hold in holds {
+        let initiator = githubuser::table
+            .filter(githubuser::id.eq(hold.fk_initiator))
+            .first::<GitHubUser>(conn)?;
+
+        w_authors.push((initiator, hold));
+    }
+
+    Ok(w_authors)
+}
+
+fn list_disposition_changes_with_authors(conn: &PgConnection, proposal_id: i32)
+    -> DashResult<Vec<(GitHubUser, FcpDispositionChange)>>
+{
+    use domain::schema::{fcp_disposition_change, githubuser};
+
+    let changes = fcp_disposition_change::table
+        .filter(fcp_disposition_change::fk_proposal.eq(proposal_id))
+        .order(fcp_disposition_change::changed_at)
+        .load::<FcpDispositionChange>(conn)?;
+
+    let mut w_authors = Vec::with_capacity(changes.len());
+
+    for change in changes {
+        let initiator = githubuser::table
+            .filter(githubuser::id.eq(change.fk_initiator))
+            .first::<GitHubUser>(conn)?;
+
+        w_authors.push((initiator, change));
+    }
+
+    Ok(w_authors)
+}
+
+fn active_hold(proposal_id: i32) -> DashResult<Option<FcpHold>> {
+    let conn = &*DB_POOL.get()?;
+    Ok(fcp_hold::table
+        .filter(fcp_hold::fk_proposal.eq(proposal_id))
+        .filter(fcp_hold::released_at.is_null())
+        .first::<FcpHold>(conn)
+        .optional()?)
+}
+
+fn resolve_applicable_feedback_requests(author: &GitHubUser,
                                         issue: &Issue,
                                         comment: &IssueComment)
                                         -> DashResult<()> {
@@ -633,13 +1263,15 @@ fn specific_subteam_members<F>(included: F) -> DashResult<Vec<GitHubUser>>
 where
     F: Fn(&String) -> bool
 {
-    resolve_logins_to_users(&SETUP.teams()
+    let setup = SETUP.get();
+    let member_logins = setup.teams()
         .filter(|&(label, _)| included(&label.0))
         .flat_map(|(_, team)| team.member_logins())
         .collect::<BTreeSet<_>>()
         .into_iter() // diesel won't work with btreeset, and dedup has weird lifetime errors
-        .collect::<Vec<_>>()
-    )
+        .collect::<Vec<_>>();
+
+    resolve_logins_to_users(&member_logins)
 }
 
 /// Return a list of all known team members.
@@ -648,41 +1280,52 @@ fn all_team_members() -> DashResult<Vec<GitHubUser>>
     specific_subteam_members(|_| true)
 }
 
-/// Check if an issue comment is written by a member of one of the subteams
-/// labelled on the issue.
-fn subteam_members(issue: &Issue) -> DashResult<Vec<GitHubUser>> {
-    // retrieve all of the teams tagged on this issue
-    specific_subteam_members(|label| issue.labels.contains(label))
-}
-
-fn cancel_fcp(author: &GitHubUser, issue: &Issue, existing: &FcpProposal) -> DashResult<()> {
+fn cancel_fcp(conn: &PgConnection, author: &GitHubUser, issue: &Issue, existing: &FcpProposal)
+    -> DashResult<()>
+{
     use domain::schema::fcp_proposal::dsl::*;
 
-    let conn = &*DB_POOL.get()?;
-
-    // if exists delete FCP with associated concerns, reviews, feedback requests
-    // db schema has ON DELETE CASCADE
-    diesel::delete(fcp_proposal.filter(id.eq(existing.id)))
-        .execute(conn)?;
-
     // leave github comment stating that FCP proposal cancelled
     let comment = RfcBotComment::new(issue, CommentType::FcpProposalCancelled(author));
-    let _ = comment.post(None);
-    &[Label::FCP,
-      Label::PFCP,
-      Label::DispositionMerge,
-      Label::DispositionClose,
-      Label::DispositionPostpone,
-    ].iter().for_each(|&lab| issue.remove_label(lab));
+
+    // keep the proposal, with its reviews and concerns, for the record, and
+    // queue the comment and label changes along with that
+    conn.transaction::<_, DashError, _>(|| {
+        diesel::update(fcp_proposal.find(existing.id))
+            .set((status.eq(ProposalStatus::Cancelled.repr()),
+                  cancelled_at.eq(Some(Utc::now().naive_utc()))))
+            .execute(conn)?;
+        insert_event(conn, &NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_proposal: Some(existing.id),
+            ..NewRfcbotEvent::new(issue.id, EventKind::FcpCancelled)
+        })?;
+
+        comment.enqueue(conn, None, false)?;
+        for &lab in &[Label::FCP,
+                      Label::PFCP,
+                      Label::DispositionMerge,
+                      Label::DispositionClose,
+                      Label::DispositionPostpone] {
+            issue.enqueue_remove_label(conn, lab)?;
+        }
+
+        Ok(())
+    })?;
+
+    issue.deliver();
 
     Ok(())
 }
 
+/// The proposal the issue is going through at the moment, if any.
 fn existing_proposal(issue: &Issue) -> DashResult<Option<FcpProposal>> {
     use domain::schema::fcp_proposal::dsl::*;
     let conn = &*DB_POOL.get()?;
     Ok(fcp_proposal
         .filter(fk_issue.eq(issue.id))
+        .filter(status.eq_any(vec![ProposalStatus::Proposed.repr(),
+                                   ProposalStatus::InFcp.repr()]))
         .first::<FcpProposal>(conn)
         .optional()?)
 }
@@ -692,7 +1335,7 @@ fn post_insert_comment(issue: &Issue, comment: CommentType) -> DashResult<IssueC
     let conn = &*DB_POOL.get()?;
 
     let comment = RfcBotComment::new(issue, comment);
-    let comment = comment.post(None)?;
+    let comment = comment.post_now()?;
     info!("Posted base comment to github, no reviewers listed yet");
 
     // at this point our new comment doesn't yet exist in the database, so
@@ -721,14 +1364,22 @@ impl<'a> RfcBotCommand<'a> {
         match self {
             StartPoll { teams, question } =>
                 process_poll(author, issue, comment, question, teams),
-            FcpPropose(disp) =>
-                process_fcp_propose(author, issue, comment, team_members, disp),
+            FcpPropose(disp, teams) =>
+                process_fcp_propose(author, issue, comment, disp, teams),
             FcpCancel => process_fcp_cancel(author, issue),
-            Reviewed => process_reviewed(author, issue),
+            ChangeDisposition(disp) =>
+                process_change_disposition(author, issue, comment, disp),
+            Reviewed => process_reviewed(author, issue, comment),
             NewConcern(concern_name) =>
                 process_new_concern(author, issue, comment, concern_name),
             ResolveConcern(concern_name) =>
                 process_resolve_concern(author, issue, comment, concern_name),
+            ResolveConcernFor { user, name } =>
+                process_resolve_concern_for(author, issue, comment, user, name),
+            TransferConcern { user, name } =>
+                process_transfer_concern(author, issue, team_members, user, name),
+            Hold(reason) => process_hold(author, issue, comment, reason),
+            Unhold => process_unhold(author, issue, comment),
             FeedbackRequest(username) =>
                 process_feedback_request(author, issue, username),
         }
@@ -743,9 +1394,10 @@ fn process_poll
     use domain::schema::poll::dsl::*;
     use domain::schema::poll_response_request;
     let conn = &*DB_POOL.get()?;
+    let setup = SETUP.get();
 
     let teams = if teams.is_empty() {
-        SETUP.teams()
+        setup.teams()
             .filter(|&(label, _)| issue.labels.contains(&label.0))
             .map(|(label, _)| &*label.0)
             .collect::<BTreeSet<_>>()
@@ -764,54 +1416,66 @@ fn process_poll
         respondents: &[],
     })?;
 
-    let teams_str = teams.iter().cloned().intersperse(",").collect::<String>();
-    let new_poll = NewPoll {
-        fk_issue: issue.id,
-        fk_initiator: author.id,
-        fk_initiating_comment: comment.id,
-        fk_bot_tracking_comment: gh_comment.id,
-        poll_question: question,
-        poll_created_at: Utc::now().naive_utc(),
-        poll_closed: false,
-        poll_teams: &*teams_str,
-    };
-    let new_poll = diesel::insert_into(poll)
-        .values(&new_poll)
-        .get_result::<Poll>(conn)?;
+    // store the poll, and queue the changes on GitHub along with it
+    conn.transaction::<_, DashError, _>(|| {
+        let teams_str = teams.iter().cloned().intersperse(",").collect::<String>();
+        let new_poll = NewPoll {
+            fk_issue: issue.id,
+            fk_initiator: author.id,
+            fk_initiating_comment: comment.id,
+            fk_bot_tracking_comment: gh_comment.id,
+            poll_question: question,
+            poll_created_at: Utc::now().naive_utc(),
+            poll_closed: false,
+            poll_teams: &*teams_str,
+        };
+        let new_poll = diesel::insert_into(poll)
+            .values(&new_poll)
+            .get_result::<Poll>(conn)?;
 
-    debug!("poll inserted into the database");
+        debug!("poll inserted into the database");
+        insert_event(conn, &NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_poll: Some(new_poll.id),
+            fk_comment: Some(comment.id),
+            ..NewRfcbotEvent::new(issue.id, EventKind::PollStarted)
+        })?;
 
-    // generate response requests for all relevant subteam members
+        // generate response requests for all relevant subteam members
 
-    let response_requests = members
-        .iter()
-        .map(|member| NewPollResponseRequest {
-            fk_poll: new_poll.id,
-            fk_respondent: member.id,
-            // let's assume the initiator has answered it
-            responded: member.id == author.id,
-        })
-        .collect::<Vec<_>>();
+        let response_requests = members
+            .iter()
+            .map(|member| NewPollResponseRequest {
+                fk_poll: new_poll.id,
+                fk_respondent: member.id,
+                // let's assume the initiator has answered it
+                responded: member.id == author.id,
+            })
+            .collect::<Vec<_>>();
 
-    diesel::insert_into(poll_response_request::table)
-        .values(&response_requests)
-        .execute(conn)?;
+        diesel::insert_into(poll_response_request::table)
+            .values(&response_requests)
+            .execute(conn)?;
 
-    // they're in the database, but now we need them paired with githubuser
+        // they're in the database, but now we need them paired with githubuser
 
-    let response_requests = list_poll_response_requests(new_poll.id)?;
+        let response_requests = list_poll_response_requests(conn, new_poll.id)?;
 
-    debug!("poll response requests inserted into the database");
+        debug!("poll response requests inserted into the database");
 
-    // we have all of the review requests, generate a new comment and post it
+        // we have all of the review requests, generate a new comment and post it
 
-    let new_gh_comment = RfcBotComment::new(issue, CommentType::QuestionAsked {
-        initiator: author,
-        teams,
-        question,
-        respondents: &*response_requests,
-    });
-    new_gh_comment.post(Some(gh_comment.id))?;
+        let new_gh_comment = RfcBotComment::new(issue, CommentType::QuestionAsked {
+            initiator: author,
+            teams,
+            question,
+            respondents: &*response_requests,
+        });
+        new_gh_comment.enqueue(conn, Some(gh_comment.id), false)?;
+        Ok(())
+    })?;
+
+    issue.deliver();
 
     debug!("github comment updated with poll respondents");
 
@@ -820,64 +1484,122 @@ fn process_poll
 
 fn process_fcp_propose
     (author: &GitHubUser, issue: &Issue, comment: &IssueComment,
-     team_members: &[GitHubUser], disp: FcpDisposition)
+     disp: FcpDisposition, teams: BTreeSet<&str>)
     -> DashResult<()>
 {
-    debug!("processing fcp proposal: {:?}", disp);
+    debug!("processing fcp proposal: {:?} for {:?}", disp, teams);
     use domain::schema::fcp_proposal::dsl::*;
     use domain::schema::fcp_review_request;
 
     if existing_proposal(issue)?.is_none() {
-        let conn = &*DB_POOL.get()?;
+        // only the teams tagged on the issue may be asked for their review
+        let labelled = labelled_teams(issue);
+        if let Some(team) = teams.iter().find(|team| !labelled.contains(**team)) {
+            throw!(DashError::Misc(Some(format!(
+                "`{}` isn't tagged on this issue, please add the label before \
+                 proposing an FCP to it", team))));
+        }
+
         // if not exists, create new FCP proposal
         info!("proposal is a new FCP, creating...");
 
+        // without teams given, the proposal is for all labelled teams
+        let setup = SETUP.get();
+        let scoped = !teams.is_empty();
+        let teams = if teams.is_empty() {
+            setup.teams()
+                .filter(|&(label, _)| issue.labels.contains(&label.0))
+                .map(|(label, _)| &*label.0)
+                .collect::<BTreeSet<_>>()
+        } else {
+            teams
+        };
+        let team_members = specific_subteam_members(|l| teams.contains(&**l))?;
+
         // leave github comment stating that FCP is proposed, ping reviewers
         let gh_comment = post_insert_comment(issue,
-            CommentType::FcpProposed(author, disp, &[], &[]))?;
+            CommentType::FcpProposed(author, disp, teams.clone(), &[], &[], &[], &[]))?;
 
-        let proposal = NewFcpProposal {
-            fk_issue: issue.id,
-            fk_initiator: author.id,
-            fk_initiating_comment: comment.id,
-            fk_bot_tracking_comment: gh_comment.id,
-            disposition: disp.repr(),
-            fcp_start: None,
-            fcp_closed: false,
-        };
-        let proposal = diesel::insert_into(fcp_proposal)
-            .values(&proposal)
-            .get_result::<FcpProposal>(conn)?;
-
-        debug!("proposal inserted into the database");
+        // store the proposal, and queue the changes on GitHub along with it
+        let conn = &*DB_POOL.get()?;
+        conn.transaction::<_, DashError, _>(|| {
+            let now = Utc::now().naive_utc();
+
+            // the outcome of an earlier FCP doesn't stand anymore
+            let superseded = diesel::update(fcp_proposal
+                    .filter(fk_issue.eq(issue.id))
+                    .filter(status.eq(ProposalStatus::Finished.repr())))
+                .set((status.eq(ProposalStatus::Superseded.repr()), superseded_at.eq(Some(now))))
+                .execute(conn)?;
+            if superseded > 0 {
+                info!("new proposal on {}#{} supersedes {} finished one(s)",
+                      issue.repository, issue.number, superseded);
+                for &lab in &[Label::FFCP,
+                              Label::DispositionMerge,
+                              Label::DispositionClose,
+                              Label::DispositionPostpone] {
+                    issue.enqueue_remove_label(conn, lab)?;
+                }
+            }
 
-        // generate review requests for all relevant subteam members
+            let teams_str = teams.iter().cloned().intersperse(",").collect::<String>();
+            let proposal = NewFcpProposal {
+                fk_issue: issue.id,
+                fk_initiator: author.id,
+                fk_initiating_comment: comment.id,
+                fk_bot_tracking_comment: gh_comment.id,
+                disposition: disp.repr(),
+                fcp_start: None,
+                fcp_closed: false,
+                fcp_teams: &*teams_str,
+                status: ProposalStatus::Proposed.repr(),
+                proposed_at: now,
+                fcp_teams_scoped: scoped,
+            };
+            let proposal = diesel::insert_into(fcp_proposal)
+                .values(&proposal)
+                .get_result::<FcpProposal>(conn)?;
+
+            debug!("proposal inserted into the database");
+            insert_event(conn, &NewRfcbotEvent {
+                fk_actor: Some(author.id),
+                fk_proposal: Some(proposal.id),
+                fk_comment: Some(comment.id),
+                detail: disp.repr(),
+                ..NewRfcbotEvent::new(issue.id, EventKind::FcpProposed)
+            })?;
+
+            // generate review requests for all relevant subteam members
+
+            let review_requests = team_members
+                .iter()
+                .map(|member| NewFcpReviewRequest {
+                    fk_proposal: proposal.id,
+                    fk_reviewer: member.id,
+                    // let's assume the initiator has reviewed it
+                    reviewed: member.id == author.id,
+                })
+                .collect::<Vec<_>>();
+
+            diesel::insert_into(fcp_review_request::table)
+                .values(&review_requests)
+                .execute(conn)?;
 
-        let review_requests = team_members
-            .iter()
-            .map(|member| NewFcpReviewRequest {
-                fk_proposal: proposal.id,
-                fk_reviewer: member.id,
-                // let's assume the initiator has reviewed it
-                reviewed: member.id == author.id,
-            })
-            .collect::<Vec<_>>();
+            // they're in the database, but now we need them paired with githubuser
 
-        diesel::insert_into(fcp_review_request::table)
-            .values(&review_requests)
-            .execute(conn)?;
+            let review_requests = list_review_requests(conn, proposal.id)?;
 
-        // they're in the database, but now we need them paired with githubuser
+            debug!("review requests inserted into the database");
 
-        let review_requests = list_review_requests(proposal.id)?;
+            // we have all of the review requests, generate a new comment and post it
 
-        debug!("review requests inserted into the database");
+            let new_gh_comment = RfcBotComment::new(issue,
+                CommentType::FcpProposed(author, disp, teams, &[], &review_requests, &[], &[]));
+            new_gh_comment.enqueue(conn, Some(gh_comment.id), true)?;
+            Ok(())
+        })?;
 
-        // we have all of the review requests, generate a new comment and post it
-
-        let new_gh_comment = RfcBotComment::new(issue,
-            CommentType::FcpProposed(author, disp, &review_requests, &[]));
-        new_gh_comment.post(Some(gh_comment.id))?;
+        issue.deliver();
         debug!("github comment updated with reviewers");
     }
 
@@ -886,12 +1608,91 @@ fn process_fcp_propose
 
 fn process_fcp_cancel(author: &GitHubUser, issue: &Issue) -> DashResult<()> {
     if let Some(existing) = existing_proposal(issue)? {
-        cancel_fcp(author, issue, &existing)?;
+        let conn = &*DB_POOL.get()?;
+        cancel_fcp(conn, author, issue, &existing)?;
+    }
+    Ok(())
+}
+
+fn process_change_disposition
+    (author: &GitHubUser, issue: &Issue, comment: &IssueComment, disp: FcpDisposition)
+    -> DashResult<()>
+{
+    use domain::schema::{fcp_disposition_change, fcp_review_request};
+
+    if let Some(mut proposal) = existing_proposal(issue)? {
+        if proposal.fcp_closed {
+            return Ok(());
+        }
+
+        let old_disp = FcpDisposition::from_str(&proposal.disposition)?;
+        if old_disp == disp {
+            throw!(DashError::Misc(Some(format!("the proposal already is to {}", disp.repr()))));
+        }
+
+        debug!("{} is changing the disposition of proposal {} from {} to {}",
+               author.login, proposal.id, old_disp.repr(), disp.repr());
+        let conn = &*DB_POOL.get()?;
+        conn.transaction::<_, DashError, _>(|| {
+            let change = NewFcpDispositionChange {
+                fk_proposal: proposal.id,
+                fk_initiator: author.id,
+                fk_initiating_comment: comment.id,
+                old_disposition: old_disp.repr(),
+                new_disposition: disp.repr(),
+                changed_at: Utc::now().naive_utc(),
+            };
+            diesel::insert_into(fcp_disposition_change::table)
+                .values(&change)
+                .execute(conn)?;
+            insert_event(conn, &NewRfcbotEvent {
+                fk_actor: Some(author.id),
+                fk_proposal: Some(proposal.id),
+                fk_comment: Some(comment.id),
+                detail: &format!("{} -> {}", old_disp.repr(), disp.repr()),
+                ..NewRfcbotEvent::new(issue.id, EventKind::DispositionChanged)
+            })?;
+
+            proposal.disposition = disp.repr().to_string();
+            diesel::update(fcp_proposal::table.find(proposal.id))
+                .set(&proposal)
+                .execute(conn)?;
+
+            issue.enqueue_remove_label(conn, old_disp.label())?;
+            issue.enqueue_add_label(conn, disp.label())?;
+
+            match SETUP.get().disposition_change_reviews(&issue.repository) {
+                DispositionChangeReviews::Keep => (),
+                DispositionChangeReviews::Reset => {
+                    // like a new proposal, the member changing it has reviewed it
+                    diesel::update(fcp_review_request::table
+                            .filter(fcp_review_request::fk_proposal.eq(proposal.id)))
+                        .set(fcp_review_request::reviewed
+                                 .eq(fcp_review_request::fk_reviewer.eq(author.id)))
+                        .execute(conn)?;
+
+                    // the new disposition needs to be reviewed before it goes into FCP
+                    back_to_pfcp(conn, issue, &mut proposal)?;
+                }
+            }
+
+            // pending proposals get their status comment updated by evaluate_pendings
+            if proposal.fcp_start.is_some() {
+                update_running_status_comment(conn, issue, &proposal)?;
+            }
+
+            Ok(())
+        })?;
+
+        issue.deliver();
     }
+
     Ok(())
 }
 
-fn process_reviewed(author: &GitHubUser, issue: &Issue) -> DashResult<()> {
+fn process_reviewed(author: &GitHubUser, issue: &Issue, comment: &IssueComment)
+    -> DashResult<()>
+{
     // set a reviewed entry for the comment author on this issue
     if let Some(proposal) = existing_proposal(issue)? {
         use domain::schema::fcp_review_request::dsl::*;
@@ -904,12 +1705,19 @@ fn process_reviewed(author: &GitHubUser, issue: &Issue) -> DashResult<()> {
             .optional()?;
 
         if let Some(mut review_request) = review_request {
-            // store an FK to the comment marking for review (not null fk here means
-            // reviewed)
-            review_request.reviewed = true;
-            diesel::update(fcp_review_request.find(review_request.id))
-                .set(&review_request)
-                .execute(conn)?;
+            if !review_request.reviewed {
+                review_request.reviewed = true;
+                diesel::update(fcp_review_request.find(review_request.id))
+                    .set(&review_request)
+                    .execute(conn)?;
+
+                record_event(NewRfcbotEvent {
+                    fk_actor: Some(author.id),
+                    fk_proposal: Some(proposal.id),
+                    fk_comment: Some(comment.id),
+                    ..NewRfcbotEvent::new(issue.id, EventKind::Reviewed)
+                });
+            }
         }
     }
 
@@ -924,14 +1732,9 @@ fn process_new_concern
     if let Some(mut proposal) = existing_proposal(issue)? {
         // check for existing concern
         use domain::schema::fcp_concern::dsl::*;
-        use domain::schema::fcp_proposal::dsl::*;
         let conn = &*DB_POOL.get()?;
 
-        let existing_concern = fcp_concern
-            .filter(fk_proposal.eq(proposal.id))
-            .filter(name.eq(concern_name))
-            .first::<FcpConcern>(conn)
-            .optional()?;
+        let existing_concern = find_concern(proposal.id, concern_name)?;
 
         if existing_concern.is_none() {
             // if not exists, create new concern with this author as creator
@@ -942,27 +1745,51 @@ fn process_new_concern
                 name: concern_name,
                 fk_initiating_comment: comment.id,
             };
-            diesel::insert_into(fcp_concern)
-                .values(&new_concern)
-                .execute(conn)?;
+            conn.transaction::<_, DashError, _>(|| {
+                diesel::insert_into(fcp_concern)
+                    .values(&new_concern)
+                    .execute(conn)?;
+                insert_event(conn, &NewRfcbotEvent {
+                    fk_actor: Some(author.id),
+                    fk_proposal: Some(proposal.id),
+                    fk_comment: Some(comment.id),
+                    detail: concern_name,
+                    ..NewRfcbotEvent::new(issue.id, EventKind::ConcernRaised)
+                })?;
+
+                // Take us out of FCP and back into PFCP if need be:
+                back_to_pfcp(conn, issue, &mut proposal)
+            })?;
+
+            issue.deliver();
+        }
+    }
 
-            // Take us out of FCP and back into PFCP if need be:
-            if proposal.fcp_start.is_some() {
-                // Update DB: FCP is not started anymore.
-                proposal.fcp_start = None;
-                let update = diesel::update(fcp_proposal.find(proposal.id))
-                                    .set(&proposal)
-                                    .execute(conn);
-                ok_or!(update, why => {
-                    error!("Unable to mark FCP {} as unstarted: {:?}", proposal.id, why);
-                    return Ok(());
-                });
+    Ok(())
+}
 
-                // Update labels:
-                let _ = issue.add_label(Label::PFCP);
-                issue.remove_label(Label::FCP);
-            }
-        }
+/// Take a proposal which is in FCP back into PFCP, e.g. because of a new concern,
+/// using `conn`, which is in the transaction making that change.
+fn back_to_pfcp(conn: &PgConnection, issue: &Issue, proposal: &mut FcpProposal)
+    -> DashResult<()>
+{
+    use domain::schema::fcp_proposal::dsl::*;
+
+    if proposal.fcp_start.is_some() {
+        // Update DB: FCP is not started anymore.
+        proposal.fcp_start = None;
+        proposal.status = ProposalStatus::Proposed.repr().to_string();
+        diesel::update(fcp_proposal.find(proposal.id))
+            .set(&*proposal)
+            .execute(conn)?;
+
+        insert_event(conn, &NewRfcbotEvent {
+            fk_proposal: Some(proposal.id),
+            ..NewRfcbotEvent::new(issue.id, EventKind::BackToProposed)
+        })?;
+
+        // Update labels:
+        enqueue_label_swap(conn, issue, Label::PFCP, Label::FCP)?;
     }
 
     Ok(())
@@ -974,32 +1801,336 @@ fn process_resolve_concern
 {
     debug!("Command is to resolve a concern ({}).", concern_name);
 
+    resolve_concern(issue, author, comment, concern_name)
+}
+
+fn process_resolve_concern_for
+    (author: &GitHubUser, issue: &Issue, comment: &IssueComment,
+     owner_login: &str, concern_name: &str)
+    -> DashResult<()>
+{
+    debug!("Command is to resolve a concern ({}) on behalf of {}.", concern_name, owner_login);
+
+    if !is_subteam_lead(issue, author)? {
+        info!("{} can't resolve concerns of others, they don't lead any relevant subteam",
+              author.login);
+        throw!(DashError::Misc(Some(
+            "only a lead of the teams involved can resolve concerns of others".to_string())));
+    }
+
+    let owner = match find_user(owner_login)? {
+        Some(owner) => owner,
+        None => return no_such_user(owner_login),
+    };
+    resolve_concern(issue, &owner, comment, concern_name)
+}
+
+/// Mark the concern with the given name, raised by (or transferred to) `owner`,
+/// as resolved by `comment`.
+fn resolve_concern
+    (issue: &Issue, owner: &GitHubUser, comment: &IssueComment, concern_name: &str)
+    -> DashResult<()>
+{
     if let Some(proposal) = existing_proposal(issue)? {
         // check for existing concern
         use domain::schema::fcp_concern::dsl::*;
         let conn = &*DB_POOL.get()?;
 
-        let existing_concern = fcp_concern
-            .filter(fk_proposal.eq(proposal.id))
-            .filter(fk_initiator.eq(author.id))
-            .filter(name.eq(concern_name))
-            .first::<FcpConcern>(conn)
-            .optional()?;
+        let mut concern = match find_concern(proposal.id, concern_name)? {
+            Some(concern) => concern,
+            None => return no_such_concern(proposal.id, concern_name),
+        };
+
+        if concern.fk_initiator != owner.id {
+            throw!(DashError::Misc(Some(format!(
+                "the concern `{}` isn't {}'s, only its owner or a team lead can resolve it",
+                concern.name, owner.login))));
+        }
+
+        // mark concern as resolved by adding resolved_comment
+        debug!("Found a matching concern ({})", concern.name);
+        concern.fk_resolved_comment = Some(comment.id);
+        diesel::update(fcp_concern.find(concern.id))
+            .set(&concern)
+            .execute(conn)?;
+        // the resolver is whoever wrote the comment, not necessarily the owner
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(comment.fk_user),
+            fk_proposal: Some(proposal.id),
+            fk_comment: Some(comment.id),
+            detail: &concern.name,
+            ..NewRfcbotEvent::new(issue.id, EventKind::ConcernResolved)
+        });
+    }
+
+    Ok(())
+}
+
+/// Find the concern of a proposal with the given name, comparing names the
+/// way `normalize_concern_name` does.
+fn find_concern(proposal_id: i32, concern_name: &str) -> DashResult<Option<FcpConcern>> {
+    let conn = &*DB_POOL.get()?;
+    let wanted = normalize_concern_name(concern_name);
+
+    let concerns = fcp_concern::table
+        .filter(fcp_concern::fk_proposal.eq(proposal_id))
+        .load::<FcpConcern>(conn)?;
+
+    Ok(concerns.into_iter().find(|concern| normalize_concern_name(&concern.name) == wanted))
+}
+
+/// Fail with a message for the author of a command naming a concern which
+/// doesn't exist, listing the open ones instead.
+fn no_such_concern(proposal_id: i32, concern_name: &str) -> DashResult<()> {
+    let conn = &*DB_POOL.get()?;
+
+    let open = fcp_concern::table
+        .filter(fcp_concern::fk_proposal.eq(proposal_id))
+        .filter(fcp_concern::fk_resolved_comment.is_null())
+        .order(fcp_concern::name)
+        .select(fcp_concern::name)
+        .load::<String>(conn)?;
+
+    let message = if open.is_empty() {
+        format!("there's no concern named `{}`, and no open concerns at all", concern_name)
+    } else {
+        format!("there's no concern named `{}`, the open concerns are: {}",
+                concern_name,
+                open.iter().map(|name| format!("`{}`", name)).join(", "))
+    };
+
+    throw!(DashError::Misc(Some(message)))
+}
+
+fn process_transfer_concern
+    (author: &GitHubUser, issue: &Issue, team_members: &[GitHubUser],
+     new_owner_login: &str, concern_name: &str)
+    -> DashResult<()>
+{
+    debug!("Command is to transfer a concern ({}) to {}.", concern_name, new_owner_login);
+
+    if !is_subteam_lead(issue, author)? {
+        info!("{} can't transfer concerns, they don't lead any relevant subteam",
+              author.login);
+        throw!(DashError::Misc(Some(
+            "only a lead of the teams involved can transfer concerns".to_string())));
+    }
+
+    // the new owner has to be able to resolve the concern themselves
+    let new_owner = match find_user(new_owner_login)? {
+        Some(new_owner) => new_owner,
+        None => return no_such_user(new_owner_login),
+    };
+    if !team_members.contains(&new_owner) {
+        info!("can't transfer concern {} to {}, they aren't on any relevant subteam",
+              concern_name, new_owner.login);
+        throw!(DashError::Misc(Some(format!(
+            "@{} isn't on any of the teams involved, so they couldn't resolve the concern",
+            new_owner.login))));
+    }
+
+    if let Some(proposal) = existing_proposal(issue)? {
+        use domain::schema::fcp_concern::dsl::*;
+        let conn = &*DB_POOL.get()?;
+
+        let mut concern = match find_concern(proposal.id, concern_name)? {
+            Some(ref concern) if concern.fk_resolved_comment.is_some() => {
+                info!("not transferring concern {}, it's resolved already", concern.name);
+                return Ok(());
+            }
+            Some(concern) => concern,
+            None => return no_such_concern(proposal.id, concern_name),
+        };
+
+        concern.fk_initiator = new_owner.id;
+        diesel::update(fcp_concern.find(concern.id))
+            .set(&concern)
+            .execute(conn)?;
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            fk_proposal: Some(proposal.id),
+            detail: &format!("{} -> {}", concern.name, new_owner.login),
+            ..NewRfcbotEvent::new(issue.id, EventKind::ConcernTransferred)
+        });
+    }
+
+    Ok(())
+}
+
+/// Is `user` a lead of one of the subteams involved in the issue?
+fn is_subteam_lead(issue: &Issue, user: &GitHubUser) -> DashResult<bool> {
+    let teams = involved_teams(issue)?;
+    Ok(SETUP.get()
+            .teams()
+            .filter(|&(label, _)| teams.contains(&label.0))
+            .any(|(_, team)| team.is_lead(&user.login)))
+}
+
+fn no_such_user(login: &str) -> DashResult<()> {
+    throw!(DashError::Misc(Some(format!("there's no user named @{} that I know of", login))))
+}
+
+fn find_user(login: &str) -> DashResult<Option<GitHubUser>> {
+    let conn = &*DB_POOL.get()?;
+    let user = githubuser::table
+        .filter(githubuser::login.eq(login))
+        .first::<GitHubUser>(conn)
+        .optional()?;
+
+    if user.is_none() {
+        info!("no user named {} is known", login);
+    }
+
+    Ok(user)
+}
+
+fn process_hold
+    (author: &GitHubUser, issue: &Issue, comment: &IssueComment, reason: &str)
+    -> DashResult<()>
+{
+    debug!("Command is to put the proposal on hold ({}).", reason);
 
-        if let Some(mut concern) = existing_concern {
-            // mark concern as resolved by adding resolved_comment
-            debug!("Found a matching concern ({})", concern_name);
-            concern.fk_resolved_comment = Some(comment.id);
-            diesel::update(fcp_concern.find(concern.id))
-                .set(&concern)
+    if let Some(proposal) = existing_proposal(issue)? {
+        if proposal.fcp_closed {
+            return Ok(());
+        }
+
+        if let Some(hold) = active_hold(proposal.id)? {
+            info!("proposal {} is already on hold ({})", proposal.id, hold.id);
+            return Ok(());
+        }
+
+        let conn = &*DB_POOL.get()?;
+        let new_hold = NewFcpHold {
+            fk_proposal: proposal.id,
+            fk_initiator: author.id,
+            fk_initiating_comment: comment.id,
+            reason,
+            held_at: Utc::now().naive_utc(),
+            fk_released_comment: None,
+            released_at: None,
+        };
+        conn.transaction::<_, DashError, _>(|| {
+            diesel::insert_into(fcp_hold::table)
+                .values(&new_hold)
                 .execute(conn)?;
+            insert_event(conn, &NewRfcbotEvent {
+                fk_actor: Some(author.id),
+                fk_proposal: Some(proposal.id),
+                fk_comment: Some(comment.id),
+                detail: reason,
+                ..NewRfcbotEvent::new(issue.id, EventKind::HoldPlaced)
+            })?;
+
+            // pending proposals get their status comment updated by evaluate_pendings
+            if proposal.fcp_start.is_some() {
+                update_running_status_comment(conn, issue, &proposal)?;
+            }
+
+            Ok(())
+        })?;
+
+        issue.deliver();
+    }
+
+    Ok(())
+}
+
+fn process_unhold(author: &GitHubUser, issue: &Issue, comment: &IssueComment)
+    -> DashResult<()>
+{
+    if let Some(mut proposal) = existing_proposal(issue)? {
+        if let Some(mut hold) = active_hold(proposal.id)? {
+            if hold.fk_initiator != author.id && !is_subteam_lead(issue, author)? {
+                info!("{} can't release hold {}, they didn't place it and don't lead \
+                       any relevant subteam", author.login, hold.id);
+                throw!(DashError::Misc(Some(
+                    "only the member who placed the hold, or a lead of the teams involved, \
+                     can release it".to_string())));
+            }
+
+            debug!("{} is releasing hold {}", author.login, hold.id);
+            let conn = &*DB_POOL.get()?;
+            conn.transaction::<_, DashError, _>(|| {
+                release_hold(conn, &mut proposal, &mut hold, Some(comment.id))?;
+                insert_event(conn, &NewRfcbotEvent {
+                    fk_actor: Some(author.id),
+                    fk_proposal: Some(proposal.id),
+                    fk_comment: Some(comment.id),
+                    ..NewRfcbotEvent::new(issue.id, EventKind::HoldReleased)
+                })?;
+
+                if proposal.fcp_start.is_some() && !proposal.fcp_closed {
+                    update_running_status_comment(conn, issue, &proposal)?;
+                }
+
+                Ok(())
+            })?;
+
+            issue.deliver();
         }
+    }
+
+    Ok(())
+}
+
+/// Release a hold. If the proposal is in FCP, the FCP is extended by the time
+/// it spent on hold.
+fn release_hold(conn: &PgConnection,
+                proposal: &mut FcpProposal,
+                hold: &mut FcpHold,
+                released_comment: Option<i32>)
+    -> DashResult<()>
+{
+    let now = Utc::now().naive_utc();
+
+    hold.fk_released_comment = released_comment;
+    hold.released_at = Some(now);
+    diesel::update(fcp_hold::table.find(hold.id))
+        .set(&*hold)
+        .execute(conn)?;
 
+    if let (Some(start), false) = (proposal.fcp_start, proposal.fcp_closed) {
+        // the clock only stopped once both the FCP and the hold had started
+        let stopped_at = ::std::cmp::max(start, hold.held_at);
+        proposal.fcp_start = Some(start + (now - stopped_at));
+        diesel::update(fcp_proposal::table.find(proposal.id))
+            .set(&*proposal)
+            .execute(conn)?;
     }
 
     Ok(())
 }
 
+/// Queue updating the status comment of a proposal which is in FCP, using
+/// `conn`, which is in the transaction changing the proposal. Pending
+/// proposals are taken care of by evaluate_pendings.
+fn update_running_status_comment(conn: &PgConnection, issue: &Issue, proposal: &FcpProposal)
+    -> DashResult<()>
+{
+    let initiator = githubuser::table.find(proposal.fk_initiator)
+                                     .first::<GitHubUser>(conn)?;
+    let reviews = list_review_requests(conn, proposal.id)?;
+    let concerns = list_concerns_with_authors(conn, proposal.id)?;
+    let holds = list_active_holds_with_authors(conn, proposal.id)?;
+    let changes = list_disposition_changes_with_authors(conn, proposal.id)?;
+
+    let teams = proposal_teams(proposal, issue);
+    let status_comment = RfcBotComment::new(issue, CommentType::FcpProposed(
+                &initiator,
+                FcpDisposition::from_str(&proposal.disposition)?,
+                teams.iter().map(|team| &**team).collect(),
+                &changes,
+                &reviews,
+                &concerns,
+                &holds));
+
+    // the issue is labelled as being in FCP, don't bring back the PFCP label
+    status_comment.enqueue(conn, Some(proposal.fk_bot_tracking_comment), false)?;
+
+    Ok(())
+}
+
 fn process_feedback_request(author: &GitHubUser, issue: &Issue, username: &str)
     -> DashResult<()>
 {
@@ -1032,6 +2163,11 @@ fn process_feedback_request(author: &GitHubUser, issue: &Issue, username: &str)
         diesel::insert_into(rfc_feedback_request)
             .values(&new_request)
             .execute(conn)?;
+        record_event(NewRfcbotEvent {
+            fk_actor: Some(author.id),
+            detail: username,
+            ..NewRfcbotEvent::new(issue.id, EventKind::FeedbackRequested)
+        });
     }
 
     Ok(())
@@ -1047,18 +2183,18 @@ struct RfcBotComment<'a> {
 enum CommentType<'a> {
     FcpProposed(&'a GitHubUser,
                 FcpDisposition,
+                BTreeSet<&'a str>,
+                &'a [(GitHubUser, FcpDispositionChange)],
                 &'a [(GitHubUser, FcpReviewRequest)],
-                &'a [(GitHubUser, FcpConcern)]),
+                &'a [(GitHubUser, FcpConcern, Option<GitHubUser>, Option<GitHubUser>)],
+                &'a [(GitHubUser, FcpHold)]),
     FcpProposalCancelled(&'a GitHubUser),
     FcpAllReviewedNoConcerns {
-        author: &'a GitHubUser,
         status_comment_id: i32,
-        added_label: bool,
+        fcp_end: NaiveDateTime,
     },
-    FcpWeekPassed {
-        author: &'a GitHubUser,
+    FcpPassed {
         status_comment_id: i32,
-        added_label: bool,
         disposition: FcpDisposition
     },
     QuestionAsked {
@@ -1067,6 +2203,10 @@ enum CommentType<'a> {
         question: &'a str,
         teams: BTreeSet<&'a str>,
     },
+    CommandFeedback {
+        author: &'a GitHubUser,
+        feedback: &'a [String],
+    },
 }
 
 impl<'a> RfcBotComment<'a> {
@@ -1081,14 +2221,6 @@ impl<'a> RfcBotComment<'a> {
         }
     }
 
-    fn couldnt_add_label<'b>(msg: &mut String, author: &'b GitHubUser, label: Label) {
-        msg.push_str("\n\n*psst @");
-        msg.push_str(&author.login);
-        msg.push_str(", I wasn't able to add the `");
-        msg.push_str(label.as_str());
-        msg.push_str("` label, please do so.*");
-    }
-
     fn format(issue: &Issue, comment_type: &CommentType) -> String {
         match *comment_type {
             CommentType::QuestionAsked { initiator, respondents, question, ref teams } => {
@@ -1104,13 +2236,20 @@ impl<'a> RfcBotComment<'a> {
                 msg
             }
 
-            CommentType::FcpProposed(initiator, disposition, reviewers, concerns) => {
+            CommentType::FcpProposed(initiator, disposition, ref teams, changes,
+                                     reviewers, concerns, holds) => {
                 let mut msg = String::from("Team member @");
                 msg.push_str(&initiator.login);
                 msg.push_str(" has proposed to ");
                 msg.push_str(disposition.repr());
-                msg.push_str(" this. The next step is review by the rest of the tagged ");
-                msg.push_str("team members:\n\n");
+                if teams.is_empty() {
+                    msg.push_str(" this. The next step is review by the rest of the tagged ");
+                    msg.push_str("team members:\n\n");
+                } else {
+                    msg.push_str(" this. The next step is review by the rest of the members of ");
+                    msg.extend(teams.iter().cloned().intersperse(", "));
+                    msg.push_str(":\n\n");
+                }
 
                 format_ticky_boxes(&mut msg,
                     reviewers.iter().map(|(m, rr)| (m, rr.reviewed)));
@@ -1121,23 +2260,69 @@ impl<'a> RfcBotComment<'a> {
                     msg.push_str("\nConcerns:\n\n");
                 }
 
-                for &(_, ref concern) in concerns {
+                for &(ref owner, ref concern, ref resolver, ref raiser) in concerns {
                     if let Some(resolved_comment_id) = concern.fk_resolved_comment {
                         msg.push_str("* ~~");
                         msg.push_str(&concern.name);
                         msg.push_str("~~ resolved by ");
+                        if let Some(ref resolver) = *resolver {
+                            msg.push_str("@");
+                            msg.push_str(&resolver.login);
+                            msg.push_str(" on behalf of @");
+                            msg.push_str(&owner.login);
+                            msg.push_str(" in ");
+                        }
                         Self::add_comment_url(issue, &mut msg, resolved_comment_id);
                         msg.push_str("\n");
 
                     } else {
                         msg.push_str("* ");
                         msg.push_str(&concern.name);
-                        msg.push_str(" (");
+                        msg.push_str(" (raised by @");
+                        msg.push_str(&raiser.as_ref().unwrap_or(owner).login);
+                        msg.push_str(" in ");
                         Self::add_comment_url(issue, &mut msg, concern.fk_initiating_comment);
+                        if raiser.is_some() {
+                            msg.push_str(", transferred to @");
+                            msg.push_str(&owner.login);
+                        }
                         msg.push_str(")\n");
                     }
                 }
 
+                if !changes.is_empty() {
+                    msg.push_str("\nThe disposition of this proposal was changed:\n\n");
+                }
+
+                for &(ref changer, ref change) in changes {
+                    msg.push_str("* from ");
+                    msg.push_str(&change.old_disposition);
+                    msg.push_str(" to ");
+                    msg.push_str(&change.new_disposition);
+                    msg.push_str(" by @");
+                    msg.push_str(&changer.login);
+                    msg.push_str(" in ");
+                    Self::add_comment_url(issue, &mut msg, change.fk_initiating_comment);
+                    msg.push_str("\n");
+                }
+
+                if !holds.is_empty() {
+                    msg.push_str("\nThis proposal is on hold:\n\n");
+                }
+
+                for &(ref holder, ref hold) in holds {
+                    msg.push_str("* ");
+                    if !hold.reason.is_empty() {
+                        msg.push_str(&hold.reason);
+                        msg.push_str(" ");
+                    }
+                    msg.push_str("(held by @");
+                    msg.push_str(&holder.login);
+                    msg.push_str(" in ");
+                    Self::add_comment_url(issue, &mut msg, hold.fk_initiating_comment);
+                    msg.push_str(")\n");
+                }
+
                 msg.push_str("\nOnce a majority of reviewers approve (and none object), this will enter its final ");
                 msg.push_str("comment period. If you spot a major issue that hasn't been raised ");
                 msg.push_str("at any point in this process, please speak up!\n");
@@ -1149,14 +2334,31 @@ impl<'a> RfcBotComment<'a> {
                 msg
             }
 
+            CommentType::CommandFeedback { author, feedback } => {
+                let mut msg = String::from("@");
+                msg.push_str(&author.login);
+                msg.push_str(" I wasn't able to act on all of your comment:\n\n");
+
+                for why in feedback {
+                    msg.push_str("* ");
+                    msg.push_str(why);
+                    msg.push('\n');
+                }
+
+                msg.push_str("\nSee [this document](");
+                msg.push_str("https://github.com/anp/rfcbot-rs/blob/master/README.md");
+                msg.push_str(") for info about what commands tagged team members can give me.");
+
+                msg
+            }
+
             CommentType::FcpProposalCancelled(initiator) => {
                 format!("@{} proposal cancelled.", initiator.login)
             }
 
             CommentType::FcpAllReviewedNoConcerns {
-                author,
                 status_comment_id,
-                added_label,
+                fcp_end,
             } => {
                 let mut msg = String::new();
 
@@ -1165,16 +2367,14 @@ impl<'a> RfcBotComment<'a> {
                 Self::add_comment_url(issue, &mut msg, status_comment_id);
                 msg.push_str("). :bell:");
 
-                if !added_label {
-                    Self::couldnt_add_label(&mut msg, author, Label::FCP);
-                }
+                msg.push_str("\n\nThe final comment period will end on ");
+                msg.push_str(&fcp_end.format("%Y-%m-%d").to_string());
+                msg.push_str(".");
 
                 msg
             }
 
-            CommentType::FcpWeekPassed {
-                author,
-                added_label,
+            CommentType::FcpPassed {
                 status_comment_id,
                 disposition
             } => {
@@ -1196,10 +2396,6 @@ impl<'a> RfcBotComment<'a> {
                     _ => {},
                 }
 
-                if !added_label {
-                    Self::couldnt_add_label(&mut msg, author, Label::FFCP);
-                }
-
                 msg
             },
         }
@@ -1213,24 +2409,42 @@ impl<'a> RfcBotComment<'a> {
         msg.push_str(&to_add);
     }
 
-    fn maybe_add_pfcp_label(&self) {
-        if let CommentType::FcpProposed(_, disposition, ..) = self.comment_type {
-            let _ = self.issue.add_label(Label::PFCP);
-            let _ = self.issue.add_label(disposition.label());
-        }
+    /// Post the comment, or edit `existing_comment` to be it, and try to
+    /// deliver that right away.
+    fn post(&self, existing_comment: Option<i32>) -> DashResult<()> {
+        let conn = &*DB_POOL.get()?;
+        conn.transaction::<_, DashError, _>(|| self.enqueue(conn, existing_comment, true))?;
+        self.issue.deliver();
+        Ok(())
     }
 
-    fn post(&self, existing_comment: Option<i32>) -> DashResult<CommentFromJson> {
-        use config::CONFIG;
+    /// Queue posting the comment, or editing `existing_comment` to be it, using
+    /// `conn` so that it can be part of a transaction. Editing the tracking
+    /// comment of a pending proposal also labels the issue as such, if
+    /// `add_labels` is set.
+    fn enqueue(&self, conn: &PgConnection, existing_comment: Option<i32>, add_labels: bool)
+        -> DashResult<bool>
+    {
+        if let Some(comment_id) = existing_comment {
+            if add_labels {
+                if let CommentType::FcpProposed(_, disposition, ..) = self.comment_type {
+                    self.issue.enqueue_add_label(conn, Label::PFCP)?;
+                    self.issue.enqueue_add_label(conn, disposition.label())?;
+                }
+            }
+            self.issue.enqueue(conn, Action::EditComment(comment_id, &self.body), None)
+        } else {
+            self.issue.enqueue(conn, Action::NewComment(&self.body), None)
+        }
+    }
 
-        if CONFIG.post_comments {
+    /// Post a new comment right away rather than through the outbox, for
+    /// when we need to know its id, as with tracking comments. Should this
+    /// fail, nothing referring to the comment gets stored.
+    fn post_now(&self) -> DashResult<CommentFromJson> {
+        if posting_enabled() {
             if self.issue.open {
-                if let Some(comment_id) = existing_comment {
-                    self.maybe_add_pfcp_label();
-                    GH.edit_comment(&self.issue.repository, comment_id, &self.body)
-                } else {
-                    GH.new_comment(&self.issue.repository, self.issue.number, &self.body)
-                }
+                GH.new_comment(&self.issue.repository, self.issue.number, &self.body)
             } else {
                 info!("Skipping comment to {}#{}, the issue is no longer open",
                       self.issue.repository,
@@ -1255,3 +2469,759 @@ fn format_ticky_boxes<'a>
         msg.push('\n');
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+    use super::*;
+    use chrono::Duration;
+
+    use serde_json;
+
+    use github::{handle_comment, handle_deleted_comment, handle_issue, handle_user, ingest_since};
+    use github::fake::Call;
+    use domain::github::OutboxEntry;
+
+    /// Comment ids handed out to test comments, counting down from -1000.
+    static NEXT_COMMENT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    /// Drives a single issue, in a (fake) repository of its own, through the bot.
+    /// Everything the harness created in the database is removed when it's dropped.
+    pub struct Harness {
+        pub repo: &'static str,
+        pub issue: Issue,
+        pub members: Vec<GitHubUser>,
+    }
+
+    impl Harness {
+        /// Open a new issue labelled with the first team we know of,
+        /// and let the scraper pick it up.
+        pub fn new(repo: &'static str) -> Self {
+            cleanup(repo);
+
+            let setup = SETUP.get();
+            let (label, _) = setup.teams().next().unwrap();
+            let members = specific_subteam_members(|l| l == &label.0).unwrap();
+            assert!(members.len() >= 3, "need a team with a few members for this test");
+
+            handle_user(&*DB_POOL.get().unwrap(), &GH.bot_user()).unwrap();
+
+            let start = Utc::now() - Duration::minutes(1);
+            GH.seed_issue(repo, issue_json(&members[0], &[label.0.as_str()]));
+            ingest_since(repo, start).unwrap();
+
+            let issue = issue::table
+                .filter(issue::repository.eq(repo))
+                .filter(issue::number.eq(1))
+                .first::<Issue>(&*DB_POOL.get().unwrap())
+                .unwrap();
+
+            Harness { repo, issue, members }
+        }
+
+        /// Change the labels of the issue, as if it had arrived through a webhook.
+        pub fn relabel(&self, labels: &[&str]) {
+            let issue = serde_json::from_value(issue_json(&self.members[0], labels)).unwrap();
+            handle_issue(&*DB_POOL.get().unwrap(), issue, self.repo).unwrap();
+        }
+
+        fn comment_json(&self, id: i32, author: &GitHubUser, body: &str) -> CommentFromJson {
+            let now = Utc::now();
+            CommentFromJson {
+                id,
+                html_url: format!("https://github.com/{}/issues/{}#issuecomment-{}",
+                                  self.repo, self.issue.number, id),
+                body: body.to_string(),
+                user: author.clone(),
+                created_at: now,
+                updated_at: now,
+            }
+        }
+
+        /// Leave a new comment as `author`, as if it had arrived through a webhook.
+        pub fn comment(&self, author: &GitHubUser, body: &str) -> i32 {
+            let id = -1000 - NEXT_COMMENT.fetch_add(1, Ordering::SeqCst) as i32;
+            self.edit(id, author, body);
+            id
+        }
+
+        /// Post or edit comment `id`, as if it had arrived through a webhook.
+        pub fn edit(&self, id: i32, author: &GitHubUser, body: &str) {
+            let conn = &*DB_POOL.get().unwrap();
+            handle_comment(conn, self.comment_json(id, author, body), self.repo).unwrap();
+        }
+
+        /// Delete comment `id`, as if it had arrived through a webhook.
+        pub fn delete(&self, id: i32, author: &GitHubUser) {
+            let conn = &*DB_POOL.get().unwrap();
+            handle_deleted_comment(conn, self.comment_json(id, author, "")).unwrap();
+        }
+
+        pub fn proposal(&self) -> Option<FcpProposal> {
+            existing_proposal(&self.issue).unwrap()
+        }
+
+        pub fn concerns(&self) -> Vec<FcpConcern> {
+            let proposal = self.proposal().expect("no FCP proposal found");
+            list_concerns_with_authors(&*DB_POOL.get().unwrap(), proposal.id)
+                .unwrap()
+                .into_iter()
+                .map(|(_, concern, _, _)| concern)
+                .collect()
+        }
+
+        pub fn labels(&self) -> BTreeSet<String> {
+            GH.labels(self.repo, self.issue.number)
+        }
+
+        /// Pretend the FCP has run its course, and let the bot act on that.
+        pub fn end_fcp(&self) {
+            let proposal = self.proposal().expect("no FCP proposal found");
+            let started = proposal.fcp_start.expect("FCP should have started");
+            let disposition = FcpDisposition::from_str(&proposal.disposition).unwrap();
+            let duration = SETUP.get().fcp_duration(self.repo, disposition);
+            diesel::update(fcp_proposal::table.find(proposal.id))
+                .set(fcp_proposal::fcp_start.eq(Some(started - duration - Duration::hours(1))))
+                .execute(&*DB_POOL.get().unwrap())
+                .unwrap();
+            evaluate_nags();
+        }
+    }
+
+    /// The test issue, as GitHub would return it.
+    fn issue_json(author: &GitHubUser, labels: &[&str]) -> serde_json::Value {
+        let now = Utc::now();
+        let labels = labels.iter()
+            .map(|label| json!({ "name": label, "color": "ffffff" }))
+            .collect::<Vec<_>>();
+
+        json!({
+            "number": 1,
+            "user": author,
+            "assignee": null,
+            "state": "open",
+            "title": "rfcbot test issue",
+            "body": "",
+            "labels": labels,
+            "milestone": null,
+            "locked": false,
+            "comments": 0,
+            "closed_at": null,
+            "created_at": now,
+            "updated_at": now,
+            "comments_url": "",
+        })
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            cleanup(self.repo);
+        }
+    }
+
+    /// Remove everything the bot stored for issues in `repo`.
+    fn cleanup(repo: &str) {
+        let conn = &*DB_POOL.get().unwrap();
+
+        let issue_ids = issue::table
+            .filter(issue::repository.eq(repo))
+            .select(issue::id)
+            .load::<i32>(conn)
+            .unwrap();
+
+        diesel::delete(rfcbot_event::table.filter(rfcbot_event::fk_issue.eq_any(&issue_ids)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(github_outbox::table.filter(github_outbox::fk_issue.eq_any(&issue_ids)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(poll::table.filter(poll::fk_issue.eq_any(&issue_ids)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(fcp_proposal::table.filter(fcp_proposal::fk_issue.eq_any(issue_ids)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(issuecomment::table.filter(issuecomment::repository.eq(repo)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(github_sync_cursor::table.filter(github_sync_cursor::repository.eq(repo)))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(github_etag::table
+                .filter(github_etag::url.like(format!("/repos/{}/%", repo))))
+            .execute(conn)
+            .unwrap();
+        diesel::delete(issue::table.filter(issue::repository.eq(repo)))
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn fcp_merge_flow() {
+        let h = Harness::new("rfcbot-test/fcp-merge-flow");
+
+        // propose
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+
+        let proposed = h.proposal().expect("proposal should have been created");
+        assert_eq!(proposed.disposition, FcpDisposition::Merge.repr());
+        assert!(proposed.fcp_start.is_none());
+        assert!(h.labels().contains(Label::PFCP.as_str()));
+        assert!(h.labels().contains(Label::DispositionMerge.as_str()));
+
+        // review -> FCP
+        for member in &h.members[1..] {
+            h.comment(member, "@rfcbot reviewed");
+        }
+
+        let in_fcp = h.proposal().unwrap();
+        assert!(in_fcp.fcp_start.is_some(), "FCP should have started");
+        assert!(h.labels().contains(Label::FCP.as_str()));
+        assert!(!h.labels().contains(Label::PFCP.as_str()));
+
+        // pretend the FCP has run its course -> FFCP
+        h.end_fcp();
+
+        let finished = fcp_proposal::table
+            .find(in_fcp.id)
+            .first::<FcpProposal>(&*DB_POOL.get().unwrap())
+            .unwrap();
+        assert!(finished.fcp_closed);
+        assert_eq!(finished.status, ProposalStatus::Finished.repr());
+        assert!(finished.finished_at.is_some());
+        assert!(h.proposal().is_none());
+        assert!(h.labels().contains(Label::FFCP.as_str()));
+        assert!(!h.labels().contains(Label::FCP.as_str()));
+        assert!(!GH.is_closed(h.repo, h.issue.number));
+
+        // tracking comment, FCP start, FCP end
+        let new_comments = GH.calls_for(h.repo).into_iter().filter(|c| match *c {
+            Call::NewComment { .. } => true,
+            _ => false,
+        }).count();
+        assert_eq!(new_comments, 3);
+        assert!(GH.comment_bodies(h.repo, h.issue.number)
+                  .iter()
+                  .any(|body| body.contains("is now **complete**")));
+    }
+
+    #[test]
+    fn edited_comments_process_new_commands() {
+        let h = Harness::new("rfcbot-test/edited-comments");
+        let author = &h.members[0];
+
+        h.comment(author, "@rfcbot fcp close");
+        let proposal = h.proposal().unwrap();
+
+        // adding a concern by editing
+        let id = h.comment(author, "I have a concern.");
+        assert!(h.concerns().is_empty());
+        h.edit(id, author, "I have a concern.\n@rfcbot concern naming");
+        assert_eq!(h.concerns().len(), 1);
+
+        // re-editing the same comment doesn't process the old commands again
+        h.edit(id, author, "I have a concern.\n@rfcbot concern naming\n@rfcbot fcp merge");
+        assert_eq!(h.concerns().len(), 1);
+        assert_eq!(h.proposal().unwrap(), proposal);
+    }
+
+    #[test]
+    fn deleted_comments_are_undone() {
+        let h = Harness::new("rfcbot-test/deleted-comments");
+        let author = &h.members[0];
+
+        h.comment(author, "@rfcbot fcp postpone");
+
+        // deleting a concern drops it
+        let concern = h.comment(author, "@rfcbot concern naming");
+        assert_eq!(h.concerns().len(), 1);
+        h.delete(concern, author);
+        assert!(h.concerns().is_empty());
+
+        // deleting a resolution reopens the concern
+        h.comment(author, "@rfcbot concern syntax");
+        let resolution = h.comment(author, "@rfcbot resolve syntax");
+        assert!(h.concerns()[0].fk_resolved_comment.is_some());
+        h.delete(resolution, author);
+        assert!(h.concerns()[0].fk_resolved_comment.is_none());
+
+        // deleting the proposal cancels it
+        let proposal = h.proposal().unwrap();
+        h.delete(proposal.fk_initiating_comment, author);
+        assert!(h.proposal().is_none());
+        assert!(!h.labels().contains(Label::PFCP.as_str()));
+    }
+
+    #[test]
+    fn holds_stop_the_fcp_clock() {
+        let h = Harness::new("rfcbot-test/holds");
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        h.comment(&h.members[1], "@rfcbot hold waiting for the crater run");
+
+        // a hold keeps the proposal from entering FCP
+        for member in &h.members[1..] {
+            h.comment(member, "@rfcbot reviewed");
+        }
+        assert!(h.proposal().unwrap().fcp_start.is_none());
+        assert!(GH.comment_bodies(h.repo, h.issue.number)
+                  .iter()
+                  .any(|body| body.contains("waiting for the crater run")));
+
+        // only the member who placed it can release it...
+        h.comment(&h.members[2], "@rfcbot unhold");
+        assert!(h.proposal().unwrap().fcp_start.is_none());
+        assert!(GH.comment_bodies(h.repo, h.issue.number)
+                  .pop()
+                  .unwrap()
+                  .contains("only the member who placed the hold"));
+        h.comment(&h.members[1], "@rfcbot unhold");
+        let in_fcp = h.proposal().unwrap();
+        assert!(in_fcp.fcp_start.is_some());
+
+        // pretend the FCP has run its course, but was put on hold two hours ago
+        h.comment(&h.members[1], "@rfcbot hold let's wait for the new RFC");
+        let now = Utc::now().naive_utc();
+        let duration = SETUP.get().fcp_duration(h.repo, FcpDisposition::Merge);
+        let started = now - duration - Duration::hours(1);
+        diesel::update(fcp_proposal::table.find(in_fcp.id))
+            .set(fcp_proposal::fcp_start.eq(Some(started)))
+            .execute(&*DB_POOL.get().unwrap())
+            .unwrap();
+        diesel::update(fcp_hold::table.filter(fcp_hold::fk_proposal.eq(in_fcp.id))
+                                      .filter(fcp_hold::released_at.is_null()))
+            .set(fcp_hold::held_at.eq(now - Duration::hours(2)))
+            .execute(&*DB_POOL.get().unwrap())
+            .unwrap();
+
+        // the FCP doesn't finish while on hold...
+        evaluate_nags();
+        assert!(!h.proposal().unwrap().fcp_closed);
+
+        // ...and is extended by the time it was on hold once released, here
+        // by a lead of the team
+        let label = SETUP.get().team_labels().next().unwrap().0.clone();
+        let _lead = ::teams::test::add_lead(&label, &h.members[2].login);
+        h.comment(&h.members[2], "@rfcbot unhold");
+        let released = h.proposal().unwrap();
+        assert!(!released.fcp_closed);
+        assert!(released.fcp_start.unwrap() >= started + Duration::hours(2));
+        assert!(!h.labels().contains(Label::FFCP.as_str()));
+        assert!(!h.labels().contains(Label::PFCP.as_str()));
+    }
+
+    #[test]
+    fn relabelling_updates_reviewers() {
+        let h = Harness::new("rfcbot-test/relabelling");
+        let setup = SETUP.get();
+        let mut labels = setup.team_labels().map(|label| &*label.0);
+        let (first, second) = (labels.next().unwrap(), labels.next().unwrap());
+        let second_members = specific_subteam_members(|l| l == second).unwrap();
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        let proposal = h.proposal().unwrap();
+        let reviewers = || list_review_requests(&*DB_POOL.get().unwrap(), proposal.id)
+            .unwrap()
+            .into_iter()
+            .map(|(reviewer, _)| reviewer)
+            .collect::<BTreeSet<_>>();
+
+        // adding a team asks its members for a review too
+        h.relabel(&[first, second]);
+        assert!(h.members.iter().all(|m| reviewers().contains(m)));
+        assert!(second_members.iter().all(|m| reviewers().contains(m)));
+
+        // removing one drops those who aren't on any other labelled team
+        h.relabel(&[second]);
+        assert_eq!(reviewers(), second_members.iter().cloned().collect());
+
+        // the tracking comment lists the new reviewers
+        let tracking_comment = &GH.comment_bodies(h.repo, h.issue.number)[0];
+        assert!(second_members.iter().all(|m| tracking_comment.contains(&format!("@{}", m.login))));
+    }
+
+    #[test]
+    fn rejected_commands_get_a_reply() {
+        let h = Harness::new("rfcbot-test/command-feedback");
+        let outsider = GitHubUser {
+            id: -77,
+            login: "rfcbot-outsider".to_string(),
+        };
+        let replies = || GH.comment_bodies(h.repo, h.issue.number)
+            .into_iter()
+            .filter(|body| body.contains("wasn't able to act"))
+            .collect::<Vec<_>>();
+
+        // outsiders are told which team may start an FCP
+        h.comment(&outsider, "@rfcbot fcp merge");
+        assert!(h.proposal().is_none());
+        assert_eq!(replies().len(), 1);
+        assert!(replies()[0].starts_with("@rfcbot-outsider"));
+        assert!(labelled_teams(&h.issue).iter().all(|team| replies()[0].contains(team.as_str())));
+
+        // but not again right away
+        h.comment(&outsider, "@rfcbot fcp close");
+        assert_eq!(replies().len(), 1);
+
+        // typos get a suggestion
+        h.comment(&h.members[0], "@rfcbot fcp mrege");
+        assert_eq!(replies().len(), 2);
+        assert!(replies()[1].contains("did you mean `merge`?"));
+        assert!(h.proposal().is_none());
+
+        // the replies are kept in the audit log, so the rate limit survives restarts
+        let (_, history) = ::nag::issue_history(h.repo, h.issue.number).unwrap();
+        assert_eq!(history.iter()
+                       .filter(|e| e.event.kind == EventKind::CommandFeedback.repr())
+                       .count(), 2);
+    }
+
+    #[test]
+    fn unauthorized_command_doesnt_stop_the_others() {
+        let h = Harness::new("rfcbot-test/unauthorized-command");
+        let all_members = all_team_members().unwrap();
+        let other_member = all_members.iter()
+            .find(|m| !h.members.contains(m))
+            .expect("need a member of a team other than the first");
+
+        // the FCP is for the tagged team only, but anyone on a team may start a poll
+        h.comment(other_member, "@rfcbot fcp merge\n\n@rfcbot poll is this a good idea?");
+
+        assert!(h.proposal().is_none());
+        let polls = poll::table
+            .filter(poll::fk_issue.eq(h.issue.id))
+            .load::<Poll>(&*DB_POOL.get().unwrap())
+            .unwrap();
+        assert_eq!(polls.len(), 1);
+    }
+
+    #[test]
+    fn concern_names_are_matched_loosely() {
+        let h = Harness::new("rfcbot-test/concern-names");
+        let raiser = &h.members[1];
+        let is_resolved = |name: &str| h.concerns()
+            .into_iter()
+            .find(|c| c.name == name)
+            .map(|c| c.fk_resolved_comment.is_some())
+            .unwrap();
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        h.comment(raiser, "@rfcbot concern \"Naming things\" because it's hard");
+        h.comment(raiser, "@rfcbot concern stability");
+        assert!(!is_resolved("Naming things"));
+
+        // an unknown name gets a reply listing the open concerns
+        h.comment(raiser, "@rfcbot resolve naming");
+        let replies = GH.comment_bodies(h.repo, h.issue.number);
+        let reply = replies.last().unwrap();
+        assert!(reply.contains("there's no concern named `naming`"));
+        assert!(reply.contains("`Naming things`, `stability`"));
+
+        h.comment(raiser, "@rfcbot resolved  naming THINGS.");
+        assert!(is_resolved("Naming things"));
+        assert!(!is_resolved("stability"));
+    }
+
+    #[test]
+    fn leads_can_resolve_concerns_for_others() {
+        let h = Harness::new("rfcbot-test/resolve-for");
+        let (lead, raiser, member) = (&h.members[0], &h.members[1], &h.members[2]);
+        let label = SETUP.get().team_labels().next().unwrap().0.clone();
+        let _lead = ::teams::test::add_lead(&label, &lead.login);
+        let last_reply = || GH.comment_bodies(h.repo, h.issue.number).pop().unwrap();
+
+        h.comment(lead, "@rfcbot fcp merge");
+        h.comment(raiser, "@rfcbot concern naming");
+
+        // other members can't resolve it for the raiser
+        h.comment(member, &format!("@rfcbot resolve-for @{} naming", raiser.login));
+        assert!(h.concerns()[0].fk_resolved_comment.is_none());
+        assert!(last_reply().contains("only a lead of the teams involved"));
+
+        // the lead has to name an existing concern
+        h.comment(lead, &format!("@rfcbot resolve-for @{} nameing", raiser.login));
+        assert!(h.concerns()[0].fk_resolved_comment.is_none());
+        assert!(last_reply().contains("there's no concern named `nameing`"));
+
+        h.comment(lead, &format!("@rfcbot resolve-for @{} naming", raiser.login));
+        assert!(h.concerns()[0].fk_resolved_comment.is_some());
+
+        let tracking_comment = &GH.comment_bodies(h.repo, h.issue.number)[0];
+        assert!(tracking_comment.contains(&format!(
+            "~~naming~~ resolved by @{} on behalf of @{} in", lead.login, raiser.login)));
+    }
+
+    #[test]
+    fn leads_can_transfer_concerns() {
+        let h = Harness::new("rfcbot-test/transfer-concern");
+        let (lead, raiser, member) = (&h.members[0], &h.members[1], &h.members[2]);
+        let label = SETUP.get().team_labels().next().unwrap().0.clone();
+        let _lead = ::teams::test::add_lead(&label, &lead.login);
+        let outsider = all_team_members()
+            .unwrap()
+            .into_iter()
+            .find(|m| !h.members.contains(m))
+            .expect("need a member of a team other than the first");
+        let last_reply = || GH.comment_bodies(h.repo, h.issue.number).pop().unwrap();
+        let owner = || h.concerns()[0].fk_initiator;
+
+        h.comment(lead, "@rfcbot fcp merge");
+        h.comment(raiser, "@rfcbot concern naming");
+
+        // only leads may transfer concerns
+        h.comment(member, &format!("@rfcbot transfer @{} naming", member.login));
+        assert_eq!(owner(), raiser.id);
+        assert!(last_reply().contains("only a lead of the teams involved"));
+
+        // and only existing ones
+        h.comment(lead, &format!("@rfcbot transfer @{} nameing", member.login));
+        assert_eq!(owner(), raiser.id);
+        assert!(last_reply().contains("there's no concern named `nameing`"));
+
+        // to someone who could resolve them
+        h.comment(lead, &format!("@rfcbot transfer @{} naming", outsider.login));
+        assert_eq!(owner(), raiser.id);
+
+        h.comment(lead, &format!("@rfcbot transfer @{} naming", member.login));
+        assert_eq!(owner(), member.id);
+
+        let tracking_comment = &GH.comment_bodies(h.repo, h.issue.number)[0];
+        assert!(tracking_comment.contains(&format!("* naming (raised by @{} in", raiser.login)));
+        assert!(tracking_comment.contains(&format!(", transferred to @{})", member.login)));
+
+        // the new owner resolves it like their own
+        h.comment(member, "@rfcbot resolve naming");
+        assert!(h.concerns()[0].fk_resolved_comment.is_some());
+    }
+
+    #[test]
+    fn proposals_can_be_scoped_to_teams() {
+        let h = Harness::new("rfcbot-test/scoped-proposal");
+        let setup = SETUP.get();
+        let mut labels = setup.team_labels().map(|label| &*label.0);
+        let (first, second) = (labels.next().unwrap(), labels.next().unwrap());
+        h.relabel(&[first, second]);
+
+        h.comment(&h.members[0], &format!("@rfcbot fcp merge {}", first));
+
+        let proposal = h.proposal().unwrap();
+        assert_eq!(proposal.fcp_teams, first);
+        let reviewers = list_review_requests(&*DB_POOL.get().unwrap(), proposal.id)
+            .unwrap()
+            .into_iter()
+            .map(|(reviewer, _)| reviewer)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(reviewers, h.members.iter().cloned().collect());
+
+        let tracking_comment = &GH.comment_bodies(h.repo, h.issue.number)[0];
+        assert!(tracking_comment.contains(&format!("review by the rest of the members of {}:", first)));
+
+        // the named teams stay in charge whatever happens to the labels
+        h.relabel(&[second]);
+        h.relabel(&[]);
+        let proposal = h.proposal().unwrap();
+        assert_eq!(proposal.fcp_teams, first);
+        assert!(proposal.fcp_teams_scoped);
+        let conn = &*DB_POOL.get().unwrap();
+        assert_eq!(list_review_requests(conn, proposal.id).unwrap().len(), h.members.len());
+    }
+
+    #[test]
+    fn unscoped_proposals_follow_the_labels() {
+        let h = Harness::new("rfcbot-test/unscoped-proposal");
+        let setup = SETUP.get();
+        let mut labels = setup.team_labels().map(|label| &*label.0);
+        let (first, second) = (labels.next().unwrap(), labels.next().unwrap());
+        h.relabel(&[first]);
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        let proposal = h.proposal().unwrap();
+        assert_eq!(proposal.fcp_teams, first);
+        assert!(!proposal.fcp_teams_scoped);
+
+        h.relabel(&[first, second]);
+        let both = [first, second].iter().map(|team| team.to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(h.proposal().unwrap().teams(&BTreeSet::new()), both);
+
+        // without any team labels left, it's still not mistaken for a scoped proposal
+        h.relabel(&[]);
+        let proposal = h.proposal().unwrap();
+        assert!(!proposal.fcp_teams_scoped);
+        assert!(proposal.teams(&BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn scoped_proposals_are_for_their_teams_only() {
+        let h = Harness::new("rfcbot-test/scoped-authorization");
+        let setup = SETUP.get();
+        let mut labels = setup.team_labels().map(|label| &*label.0);
+        let (first, second, third) =
+            (labels.next().unwrap(), labels.next().unwrap(), labels.next().unwrap());
+        let outsider = specific_subteam_members(|l| l == second)
+            .unwrap()
+            .into_iter()
+            .find(|m| !h.members.contains(m))
+            .expect("need a member of the second team who isn't on the first");
+        let replies = || GH.comment_bodies(h.repo, h.issue.number)
+            .into_iter()
+            .filter(|body| body.contains("wasn't able to act"))
+            .collect::<Vec<_>>();
+        h.relabel(&[first, second]);
+
+        // teams which aren't tagged can't be asked for a review
+        h.comment(&h.members[0], &format!("@rfcbot fcp merge {}", third));
+        assert!(h.proposal().is_none());
+        assert!(replies()[0].contains(&format!("`{}` isn't tagged on this issue", third)));
+
+        // and the other tagged teams have no say in a proposal to one of them
+        h.comment(&h.members[0], &format!("@rfcbot fcp merge {}", first));
+        h.comment(&outsider, "@rfcbot concern not-ours");
+        assert!(h.concerns().is_empty());
+        assert!(replies().last().unwrap().contains(&format!("can only be used by members of `{}`", first)));
+    }
+
+    #[test]
+    fn disposition_can_be_changed() {
+        let h = Harness::new("rfcbot-test/change-disposition");
+        let changer = &h.members[1];
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        h.comment(changer, "@rfcbot fcp change-disposition close");
+
+        let proposal = h.proposal().unwrap();
+        assert_eq!(proposal.disposition, FcpDisposition::Close.repr());
+        assert!(h.labels().contains(Label::DispositionClose.as_str()));
+        assert!(!h.labels().contains(Label::DispositionMerge.as_str()));
+
+        // reviews are reset, except for the member who changed the disposition
+        let conn = &*DB_POOL.get().unwrap();
+        for (reviewer, request) in list_review_requests(conn, proposal.id).unwrap() {
+            assert_eq!(request.reviewed, &reviewer == changer, "{}", reviewer.login);
+        }
+
+        let tracking_comment = &GH.comment_bodies(h.repo, h.issue.number)[0];
+        assert!(tracking_comment.contains("has proposed to close this"));
+        assert!(tracking_comment.contains(&format!("* from merge to close by @{} in", changer.login)));
+    }
+
+    #[test]
+    fn issues_keep_their_proposal_history() {
+        let h = Harness::new("rfcbot-test/proposal-history");
+        let author = &h.members[0];
+        let all_proposals = || fcp_proposal::table
+            .filter(fcp_proposal::fk_issue.eq(h.issue.id))
+            .order(fcp_proposal::id)
+            .load::<FcpProposal>(&*DB_POOL.get().unwrap())
+            .unwrap();
+
+        // a cancelled proposal is kept, along with its concerns
+        h.comment(author, "@rfcbot fcp merge");
+        h.comment(author, "@rfcbot concern naming");
+        h.comment(author, "@rfcbot fcp cancel");
+        assert!(h.proposal().is_none());
+        let cancelled = all_proposals().remove(0);
+        assert_eq!(cancelled.status, ProposalStatus::Cancelled.repr());
+        assert!(cancelled.cancelled_at.is_some());
+        let conn = &*DB_POOL.get().unwrap();
+        assert_eq!(list_concerns_with_authors(conn, cancelled.id).unwrap().len(), 1);
+
+        // and the issue can go through another one
+        h.comment(author, "@rfcbot fcp postpone");
+        let proposal = h.proposal().unwrap();
+        assert_eq!(proposal.disposition, FcpDisposition::Postpone.repr());
+        assert_eq!(proposal.status, ProposalStatus::Proposed.repr());
+        assert!(h.concerns().is_empty());
+
+        // once it's finished, a new proposal supersedes it
+        diesel::update(fcp_proposal::table.find(proposal.id))
+            .set((fcp_proposal::status.eq(ProposalStatus::Finished.repr()),
+                  fcp_proposal::fcp_closed.eq(true)))
+            .execute(&*DB_POOL.get().unwrap())
+            .unwrap();
+        h.comment(author, "@rfcbot fcp merge");
+
+        let statuses = all_proposals()
+            .into_iter()
+            .map(|proposal| proposal.status)
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec!["cancelled", "superseded", "proposed"]);
+    }
+
+    #[test]
+    fn github_changes_are_delivered_in_order() {
+        let h = Harness::new("rfcbot-test/outbox");
+        let conn = &*DB_POOL.get().unwrap();
+        let pending = || github_outbox::table
+            .filter(github_outbox::fk_issue.eq(h.issue.id))
+            .filter(github_outbox::delivered_at.is_null())
+            .filter(github_outbox::gave_up_at.is_null())
+            .order(github_outbox::id)
+            .load::<OutboxEntry>(conn)
+            .unwrap();
+
+        // the fake fails edits of comments it doesn't know
+        let edit = outbox::enqueue(conn, &h.issue, Action::EditComment(1, "edited"))
+            .unwrap()
+            .unwrap();
+        assert!(outbox::deliver_for_issue(h.issue.id).is_err());
+
+        let failed = pending();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].attempts, 1);
+        assert!(failed[0].last_error.is_some());
+        assert!(failed[0].next_attempt_at > Utc::now().naive_utc());
+
+        // later changes are queued all the same, and wait for the earlier ones
+        diesel::update(github_outbox::table.find(edit))
+            .set(github_outbox::next_attempt_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+            .unwrap();
+        assert!(h.issue.enqueue_add_label(conn, Label::PFCP).unwrap());
+        h.issue.deliver();
+        assert_eq!(pending().len(), 2);
+        assert_eq!(pending()[0].attempts, 2);
+        assert!(!h.labels().contains(Label::PFCP.as_str()));
+
+        // until those are retried, or given up on
+        diesel::update(github_outbox::table.find(edit))
+            .set((github_outbox::attempts.eq(100),
+                  github_outbox::next_attempt_at.eq(Utc::now().naive_utc())))
+            .execute(conn)
+            .unwrap();
+        outbox::deliver_due().unwrap();
+
+        assert!(pending().is_empty());
+        assert!(h.labels().contains(Label::PFCP.as_str()));
+        let given_up = github_outbox::table.find(edit).first::<OutboxEntry>(conn).unwrap();
+        assert!(given_up.gave_up_at.is_some() && given_up.delivered_at.is_none());
+    }
+
+    #[test]
+    fn github_changes_are_rolled_back_with_the_rest() {
+        let h = Harness::new("rfcbot-test/outbox-transaction");
+        let conn = &*DB_POOL.get().unwrap();
+        let queued = || github_outbox::table
+            .filter(github_outbox::fk_issue.eq(h.issue.id))
+            .count()
+            .get_result::<i64>(conn)
+            .unwrap();
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        for member in &h.members[1..] {
+            h.comment(member, "@rfcbot reviewed");
+        }
+        assert!(h.proposal().unwrap().fcp_start.is_some());
+        let before = queued();
+
+        // the labels aren't swapped back if the proposal can't be taken out of FCP
+        let back = conn.transaction::<(), DashError, _>(|| {
+            let mut proposal = h.proposal().unwrap();
+            back_to_pfcp(conn, &h.issue, &mut proposal)?;
+            throw!(DashError::Misc(None))
+        });
+        assert!(back.is_err());
+        assert!(h.proposal().unwrap().fcp_start.is_some());
+        assert_eq!(queued(), before);
+    }
+}
@@ -0,0 +1,256 @@
+//! Changes the bot makes on GitHub (comments, labels, closing issues) go
+//! through the `github_outbox` table: they're written to the database, if
+//! possible along with the state change they belong to, and delivered from
+//! there. Deliveries which fail are retried with exponential backoff, so that
+//! GitHub being unavailable for a while doesn't leave its labels and comments
+//! out of sync with our database.
+
+use std::cmp;
+use std::sync::Mutex;
+use std::thread::{self, spawn, JoinHandle};
+use std::time::Duration as StdDuration;
+
+use chrono::{Duration, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel;
+
+use DB_POOL;
+use domain::github::{Issue, NewOutboxEntry, OutboxEntry};
+use domain::schema::{github_outbox, issue};
+use error::{DashError, DashResult};
+use super::{GH, posting_enabled};
+use super::client::GitHubApi;
+
+/// How often the background worker looks for deliveries to retry.
+const RETRY_INTERVAL_SECS: u64 = 60;
+
+/// Deliveries are retried after 1, 2, 4, ... minutes, but at least every this often.
+const MAX_BACKOFF_MINS: i64 = 6 * 60;
+
+/// After this many failed attempts a delivery is given up on, so that it
+/// doesn't hold up the changes queued after it for the same issue forever.
+const MAX_ATTEMPTS: i32 = 10;
+
+lazy_static! {
+    /// Held while delivering, so that entries aren't delivered twice by the
+    /// worker and the code which queued them at the same time.
+    static ref DELIVERY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action<'a> {
+    NewComment(&'a str),
+    EditComment(i32, &'a str),
+    AddLabel(&'a str),
+    RemoveLabel(&'a str),
+    CloseIssue,
+}
+
+impl<'a> Action<'a> {
+    fn repr(&self) -> &'static str {
+        match *self {
+            Action::NewComment(_) => "new-comment",
+            Action::EditComment(..) => "edit-comment",
+            Action::AddLabel(_) => "add-label",
+            Action::RemoveLabel(_) => "remove-label",
+            Action::CloseIssue => "close-issue",
+        }
+    }
+
+    fn from_entry(entry: &'a OutboxEntry) -> DashResult<Self> {
+        Ok(match (&*entry.action, entry.comment_id) {
+            ("new-comment", _) => Action::NewComment(&entry.body),
+            ("edit-comment", Some(comment_id)) => Action::EditComment(comment_id, &entry.body),
+            ("add-label", _) => Action::AddLabel(&entry.label),
+            ("remove-label", _) => Action::RemoveLabel(&entry.label),
+            ("close-issue", _) => Action::CloseIssue,
+            _ => throw!(DashError::Misc(Some(format!("invalid outbox entry {}", entry.id)))),
+        })
+    }
+}
+
+/// Queue a change to an issue on GitHub, using `conn` so that it can be part
+/// of a transaction. Returns the id of the outbox entry, or `None` if nothing
+/// was queued because posting is disabled or comments aren't wanted anymore.
+pub fn enqueue(conn: &PgConnection, issue: &Issue, action: Action) -> DashResult<Option<i32>> {
+    if !posting_enabled() {
+        info!("Skipping {} on {}#{}, posting is disabled.",
+              action.repr(), issue.repository, issue.number);
+        return Ok(None);
+    }
+
+    let comment_id = match action {
+        Action::NewComment(_) | Action::EditComment(..) if !issue.open => {
+            info!("Skipping comment to {}#{}, the issue is no longer open",
+                  issue.repository, issue.number);
+            return Ok(None);
+        }
+        Action::EditComment(comment_id, _) => {
+            // only the most recent edit of a comment needs to be delivered
+            diesel::delete(github_outbox::table
+                    .filter(github_outbox::fk_issue.eq(issue.id))
+                    .filter(github_outbox::delivered_at.is_null())
+                    .filter(github_outbox::gave_up_at.is_null())
+                    .filter(github_outbox::action.eq(action.repr()))
+                    .filter(github_outbox::comment_id.eq(comment_id)))
+                .execute(conn)?;
+            Some(comment_id)
+        }
+        _ => None,
+    };
+
+    let now = Utc::now().naive_utc();
+    let entry = NewOutboxEntry {
+        fk_issue: issue.id,
+        action: action.repr(),
+        comment_id,
+        body: match action {
+            Action::NewComment(body) | Action::EditComment(_, body) => body,
+            _ => "",
+        },
+        label: match action {
+            Action::AddLabel(label) | Action::RemoveLabel(label) => label,
+            _ => "",
+        },
+        created_at: now,
+        next_attempt_at: now,
+    };
+
+    let id = diesel::insert_into(github_outbox::table)
+        .values(&entry)
+        .returning(github_outbox::id)
+        .get_result::<i32>(conn)?;
+
+    Ok(Some(id))
+}
+
+/// The changes queued for an issue which haven't been delivered yet.
+fn pending(fk_issue: i32) -> github_outbox::BoxedQuery<'static, ::diesel::pg::Pg> {
+    github_outbox::table
+        .filter(github_outbox::fk_issue.eq(fk_issue))
+        .filter(github_outbox::delivered_at.is_null())
+        .filter(github_outbox::gave_up_at.is_null())
+        .into_boxed()
+}
+
+/// Deliver the changes queued for an issue, in the order they were queued.
+/// Stops at the first one which fails, or which is waiting to be retried,
+/// and returns the error in the former case.
+pub fn deliver_for_issue(fk_issue: i32) -> DashResult<()> {
+    let _delivering = DELIVERY_LOCK.lock();
+    let conn = &*DB_POOL.get()?;
+
+    let issue = issue::table.find(fk_issue).first::<Issue>(conn)?;
+    let entries = pending(fk_issue)
+        .order(github_outbox::id)
+        .load::<OutboxEntry>(conn)?;
+
+    for mut entry in entries {
+        let now = Utc::now().naive_utc();
+        if entry.next_attempt_at > now {
+            debug!("outbox entry {} for {}#{} is waiting to be retried",
+                   entry.id, issue.repository, issue.number);
+            break;
+        }
+
+        entry.attempts += 1;
+        let result = Action::from_entry(&entry).and_then(|action| send(&issue, action));
+        match result {
+            Ok(()) => {
+                entry.delivered_at = Some(now);
+                entry.last_error = None;
+                diesel::update(github_outbox::table.find(entry.id))
+                    .set(&entry)
+                    .execute(conn)?;
+            }
+            Err(why) => {
+                entry.last_error = Some(format!("{:?}", why));
+                if entry.attempts >= MAX_ATTEMPTS {
+                    error!("Giving up on {} for {}#{} (outbox entry {}) after {} attempts: {:?}",
+                           entry.action, issue.repository, issue.number,
+                           entry.id, entry.attempts, why);
+                    entry.gave_up_at = Some(now);
+                    diesel::update(github_outbox::table.find(entry.id))
+                        .set(&entry)
+                        .execute(conn)?;
+                    continue;
+                }
+
+                entry.next_attempt_at = now + backoff(entry.attempts);
+                warn!("Unable to deliver {} for {}#{} (outbox entry {}), retrying at {}: {:?}",
+                      entry.action, issue.repository, issue.number,
+                      entry.id, entry.next_attempt_at, why);
+                diesel::update(github_outbox::table.find(entry.id))
+                    .set(&entry)
+                    .execute(conn)?;
+                throw!(why);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// How long to wait before the next attempt, after `attempts` failed ones.
+fn backoff(attempts: i32) -> Duration {
+    let mins = 1i64 << cmp::min(cmp::max(attempts - 1, 0), 16);
+    Duration::minutes(cmp::min(mins, MAX_BACKOFF_MINS))
+}
+
+fn send(issue: &Issue, action: Action) -> DashResult<()> {
+    let repo = &issue.repository;
+    match action {
+        Action::NewComment(body) => GH.new_comment(repo, issue.number, body).map(|_| ()),
+        Action::EditComment(comment_id, body) => GH.edit_comment(repo, comment_id, body).map(|_| ()),
+        Action::AddLabel(label) => GH.add_label(repo, issue.number, label),
+        Action::RemoveLabel(label) => GH.remove_label(repo, issue.number, label),
+        Action::CloseIssue => GH.close_issue(repo, issue.number),
+    }
+}
+
+/// Deliver whatever is due for all issues.
+pub fn deliver_due() -> DashResult<()> {
+    let conn = &*DB_POOL.get()?;
+
+    let issues = github_outbox::table
+        .filter(github_outbox::delivered_at.is_null())
+        .filter(github_outbox::gave_up_at.is_null())
+        .filter(github_outbox::next_attempt_at.le(Utc::now().naive_utc()))
+        .select(github_outbox::fk_issue)
+        .distinct()
+        .load::<i32>(conn)?;
+
+    for fk_issue in issues {
+        ok_or!(deliver_for_issue(fk_issue), why =>
+            warn!("Unable to deliver queued changes for issue {}: {:?}", fk_issue, why));
+    }
+
+    Ok(())
+}
+
+pub fn start_delivering() -> JoinHandle<()> {
+    spawn(|| {
+        let sleep_duration = StdDuration::from_secs(RETRY_INTERVAL_SECS);
+        loop {
+            ok_or!(deliver_due(), why =>
+                error!("Unable to deliver queued GitHub changes: {:?}", why));
+            thread::sleep(sleep_duration);
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_a_limit() {
+        assert_eq!(backoff(1), Duration::minutes(1));
+        assert_eq!(backoff(2), Duration::minutes(2));
+        assert_eq!(backoff(5), Duration::minutes(16));
+        assert_eq!(backoff(9), Duration::minutes(256));
+        assert_eq!(backoff(10), Duration::minutes(MAX_BACKOFF_MINS));
+        assert_eq!(backoff(100), Duration::minutes(MAX_BACKOFF_MINS));
+    }
+}
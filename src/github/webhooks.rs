@@ -1,9 +1,15 @@
 use std::io::Read;
 
+use chrono::{Duration, NaiveDateTime, Utc};
+use crypto::digest::Digest;
 use crypto::hmac::Hmac;
 use crypto::mac::Mac;
 use crypto::mac::MacResult;
 use crypto::sha1::Sha1;
+use crypto::sha2::Sha256;
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
 use hex::FromHex;
 use rocket::http::Status;
 use rocket::data::{self, Data, FromData};
@@ -12,14 +18,31 @@ use rocket::outcome::Outcome::*;
 use serde_json;
 
 use config::CONFIG;
-use error::{DashError, DashResult};
+use domain::github::{NewWebhookDelivery, WebhookDelivery};
+use error::DashResult;
 use github::models::{CommentFromJson, IssueFromJson, PullRequestFromJson};
 
+/// Deliveries which haven't finished processing after this many minutes
+/// were interrupted, e.g. by a crash, and are up for replaying.
+const STALE_DELIVERY_MINS: i64 = 10;
+
+/// An authenticated webhook delivery. Its body is only parsed once the
+/// delivery has been stored, so that deliveries we fail to parse can be
+/// looked into and replayed like any other failed delivery.
 #[derive(Debug)]
 pub struct Event {
     pub delivery_id: String,
     pub event_name: String,
-    pub payload: Payload,
+    pub body: String,
+}
+
+impl Event {
+    pub fn parse(&self) -> DashResult<Payload> {
+        parse_event(&self.event_name, &self.body).map_err(|why| {
+            info!("failed to parse webhook payload of delivery {}: {:?}", self.delivery_id, why);
+            why
+        })
+    }
 }
 
 impl FromData for Event {
@@ -28,9 +51,21 @@ impl FromData for Event {
         let headers = request.headers();
 
         // see [this document](https://developer.github.com/webhooks/securing/) for more information
-        let signature = match headers.get_one("X-Hub-Signature") {
-            Some(s) => s,
-            None => return Failure((Status::BadRequest, "missing signature header")),
+        // we prefer SHA-256 signatures, and only fall back to SHA-1 if there's no SHA-256 one
+        let signature = match (headers.get_one("X-Hub-Signature-256"),
+                               headers.get_one("X-Hub-Signature")) {
+            (Some(sig), _) => match Signature::parse_sha256(sig) {
+                Some(s) => s,
+                None => return Failure((Status::BadRequest, "malformed signature header")),
+            },
+            (None, Some(_)) if CONFIG.github_webhook_reject_sha1 => {
+                return Failure((Status::Forbidden, "SHA-1 signatures are not accepted"));
+            }
+            (None, Some(sig)) => match Signature::parse_sha1(sig) {
+                Some(s) => s,
+                None => return Failure((Status::BadRequest, "malformed signature header")),
+            },
+            (None, None) => return Failure((Status::BadRequest, "missing signature header")),
         };
 
         // see [this document](https://developer.github.com/webhooks/#events) for available types
@@ -52,27 +87,11 @@ impl FromData for Event {
         }
 
         for secret in &CONFIG.github_webhook_secrets {
-            if authenticate(secret, &body, signature) {
-                // once we know it's from github, we'll parse it
-
-                let payload = match parse_event(event_name, &body) {
-                    Ok(p) => p,
-                    Err(DashError::Serde(why)) => {
-                        info!("failed to parse webhook payload: {:?}", why);
-                        return Failure((Status::BadRequest,
-                                        "failed to deserialize request payload"));
-                    }
-                    Err(why) => {
-                        error!("non-json-parsing error with webhook payload: {:?}", why);
-                        return Failure((Status::InternalServerError,
-                                        "unknown failure, check the logs"));
-                    }
-                };
-
+            if authenticate(secret, &body, &signature) {
                 let full_event = Event {
                     delivery_id: delivery_id.to_owned(),
                     event_name: event_name.to_owned(),
-                    payload: payload,
+                    body: body,
                 };
 
                 info!("Received valid webhook ({} id {})",
@@ -90,20 +109,110 @@ impl FromData for Event {
     }
 }
 
-fn authenticate(secret: &str, payload: &str, signature: &str) -> bool {
+/// The HMAC digest of a webhook payload, as sent by GitHub.
+#[derive(Debug, Eq, PartialEq)]
+enum Signature {
+    /// From the `X-Hub-Signature-256` header.
+    Sha256(Vec<u8>),
+    /// From the legacy `X-Hub-Signature` header.
+    Sha1(Vec<u8>),
+}
+
+impl Signature {
+    fn parse_sha256(header: &str) -> Option<Self> {
+        Self::parse_digest(header, "sha256", 32).map(Signature::Sha256)
+    }
+
+    fn parse_sha1(header: &str) -> Option<Self> {
+        Self::parse_digest(header, "sha1", 20).map(Signature::Sha1)
+    }
+
+    /// Parse a header of the form `<algorithm>=<hex digest of digest_len bytes>`.
+    fn parse_digest(header: &str, algorithm: &str, digest_len: usize) -> Option<Vec<u8>> {
+        let mut parts = header.trim().splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(alg), Some(hex)) if alg == algorithm =>
+                Vec::from_hex(hex).ok().filter(|digest| digest.len() == digest_len),
+            _ => None,
+        }
+    }
+}
+
+fn authenticate(secret: &str, payload: &str, signature: &Signature) -> bool {
     // https://developer.github.com/webhooks/securing/#validating-payloads-from-github
-    let sans_prefix = signature[5..].as_bytes();
-    if let Ok(sigbytes) = Vec::from_hex(sans_prefix) {
-        let mut mac = Hmac::new(Sha1::new(), secret.as_bytes());
-        mac.input(payload.as_bytes());
-        // constant time comparison
-        mac.result() == MacResult::new(&sigbytes)
-    } else {
-        false
+    match *signature {
+        Signature::Sha256(ref digest) => verify(Sha256::new(), secret, payload, digest),
+        Signature::Sha1(ref digest) => verify(Sha1::new(), secret, payload, digest),
+    }
+}
+
+fn verify<D: Digest>(hash: D, secret: &str, payload: &str, digest: &[u8]) -> bool {
+    let mut mac = Hmac::new(hash, secret.as_bytes());
+    mac.input(payload.as_bytes());
+    // constant time comparison
+    mac.result() == MacResult::new(digest)
+}
+
+/// Store a newly received delivery, so that it can be replayed later if needed.
+/// Returns `false` if we've already received a delivery with the same id,
+/// unless processing it failed, in which case GitHub redelivering it is
+/// as good as replaying it.
+pub fn record_delivery(conn: &PgConnection, event: &Event) -> DashResult<bool> {
+    use domain::schema::webhook_delivery;
+
+    let delivery = NewWebhookDelivery {
+        delivery_id: &event.delivery_id,
+        event_name: &event.event_name,
+        body: &event.body,
+        received_at: Utc::now().naive_utc(),
+    };
+
+    let inserted = diesel::insert_into(webhook_delivery::table)
+        .values(&delivery)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    if inserted == 1 {
+        return Ok(true);
     }
+
+    let retried = diesel::update(webhook_delivery::table
+            .find(&event.delivery_id)
+            .filter(webhook_delivery::successful.eq(false)))
+        .set((webhook_delivery::processed_at.eq(None::<NaiveDateTime>),
+              webhook_delivery::successful.eq(None::<bool>),
+              webhook_delivery::message.eq(None::<String>)))
+        .execute(conn)?;
+
+    Ok(retried == 1)
 }
 
-fn parse_event(event_name: &str, body: &str) -> DashResult<Payload> {
+/// Record whether processing a delivery succeeded.
+pub fn record_outcome(conn: &PgConnection, id: &str, result: &DashResult<()>) -> DashResult<()> {
+    use domain::schema::webhook_delivery::dsl::*;
+
+    let why = result.as_ref().err().map(|why| format!("{:?}", why));
+    diesel::update(webhook_delivery.find(id))
+        .set((processed_at.eq(Some(Utc::now().naive_utc())),
+              successful.eq(Some(result.is_ok())),
+              message.eq(why)))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// All deliveries which failed processing, or which never finished it, oldest first.
+pub fn failed_deliveries(conn: &PgConnection) -> DashResult<Vec<WebhookDelivery>> {
+    use domain::schema::webhook_delivery::dsl::*;
+
+    let stale = Utc::now().naive_utc() - Duration::minutes(STALE_DELIVERY_MINS);
+    Ok(webhook_delivery
+        .filter(successful.eq(false)
+                          .or(successful.is_null().and(received_at.lt(stale))))
+        .order(received_at.asc())
+        .load::<WebhookDelivery>(conn)?)
+}
+
+pub fn parse_event(event_name: &str, body: &str) -> DashResult<Payload> {
     match event_name {
         "issue_comment" => Ok(Payload::IssueComment(serde_json::from_str(body)?)),
         "issues" => Ok(Payload::Issues(serde_json::from_str(body)?)),
@@ -154,6 +263,18 @@ pub enum Payload {
     Unsupported,
 }
 
+impl Payload {
+    /// The repository the event happened in, as `owner/name`.
+    pub fn repository(&self) -> Option<&str> {
+        match *self {
+            Payload::Issues(ref event) => Some(&event.repository.full_name),
+            Payload::IssueComment(ref event) => Some(&event.repository.full_name),
+            Payload::PullRequest(ref event) => Some(&event.repository.full_name),
+            Payload::Unsupported => None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IssuesEvent {
     pub action: String,
@@ -198,3 +319,87 @@ pub struct Commit {
 pub struct Committer {
     pub login: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // https://developer.github.com/webhooks/securing/#testing-the-webhook-payload-validation
+    const SECRET: &'static str = "It's a Secret to Everybody";
+    const PAYLOAD: &'static str = "Hello, World!";
+    const SHA256: &'static str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    const SHA1: &'static str = "sha1=01dc10d0c83e72ed246219cdd91669667fe2ca59";
+
+    #[test]
+    fn authenticate_sha256() {
+        let signature = Signature::parse_sha256(SHA256).unwrap();
+        assert!(authenticate(SECRET, PAYLOAD, &signature));
+        assert!(!authenticate("wrong secret", PAYLOAD, &signature));
+        assert!(!authenticate(SECRET, "Goodbye, World!", &signature));
+    }
+
+    #[test]
+    fn authenticate_sha1() {
+        let signature = Signature::parse_sha1(SHA1).unwrap();
+        assert!(authenticate(SECRET, PAYLOAD, &signature));
+        assert!(!authenticate("wrong secret", PAYLOAD, &signature));
+        assert!(!authenticate(SECRET, "Goodbye, World!", &signature));
+    }
+
+    #[test]
+    fn reject_malformed_signatures() {
+        // wrong or missing prefix
+        assert_eq!(Signature::parse_sha256(SHA1), None);
+        assert_eq!(Signature::parse_sha1(SHA256), None);
+        assert_eq!(Signature::parse_sha256(&SHA256["sha256=".len()..]), None);
+        assert_eq!(Signature::parse_sha1("sha1"), None);
+
+        // too short to even hold the prefix, this used to panic
+        assert_eq!(Signature::parse_sha1(""), None);
+        assert_eq!(Signature::parse_sha1("sha"), None);
+
+        // not hex, or the wrong length
+        assert_eq!(Signature::parse_sha1("sha1=not-hex-at-all"), None);
+        assert_eq!(Signature::parse_sha1("sha1=01dc10d0"), None);
+        assert_eq!(Signature::parse_sha256(&SHA256[..SHA256.len() - 2]), None);
+    }
+
+    #[test]
+    fn failed_deliveries_are_processed_again() {
+        use DB_POOL;
+        use domain::schema::webhook_delivery;
+        use error::DashError;
+
+        let conn = &*DB_POOL.get().unwrap();
+        let event = Event {
+            delivery_id: "rfcbot-test-delivery".to_string(),
+            event_name: "issue_comment".to_string(),
+            body: "{ not json".to_string(),
+        };
+        let cleanup = || diesel::delete(webhook_delivery::table.find(&event.delivery_id))
+            .execute(conn)
+            .unwrap();
+        cleanup();
+
+        // stored even though it can't be parsed
+        assert!(record_delivery(conn, &event).unwrap());
+        let result = event.parse().map(|_| ());
+        assert!(result.is_err());
+        record_outcome(conn, &event.delivery_id, &result).unwrap();
+        assert!(failed_deliveries(conn).unwrap()
+            .iter()
+            .any(|d| d.delivery_id == event.delivery_id && d.body == event.body));
+
+        // GitHub redelivering a failed delivery gets it processed again
+        assert!(record_delivery(conn, &event).unwrap());
+        // while it's being processed, or once it was successful, it's skipped
+        assert!(!record_delivery(conn, &event).unwrap());
+        record_outcome(conn, &event.delivery_id, &Err(DashError::Misc(None))).unwrap();
+        assert!(record_delivery(conn, &event).unwrap());
+        record_outcome(conn, &event.delivery_id, &Ok(())).unwrap();
+        assert!(!record_delivery(conn, &event).unwrap());
+
+        cleanup();
+    }
+}
@@ -77,11 +77,13 @@ fn main() {
     let _ = DB_POOL.get().expect("Unable to test connection pool.");
 
     // we want to panic if we're unable to find any of the usernames
-    let parsed_teams = teams::SETUP.team_labels().collect::<Vec<_>>();
+    let setup = teams::SETUP.get();
+    let parsed_teams = setup.team_labels().collect::<Vec<_>>();
     info!("parsed teams: {:?}", parsed_teams);
 
     // FIXME(anp) need to handle panics in both the listeners and crash the server
     let _ = scraper::start_scraping();
+    let _ = github::outbox::start_delivering();
     let _server_handle = server::serve();
 
     // block
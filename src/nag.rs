@@ -1,9 +1,15 @@
+use std::collections::BTreeSet;
+
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 use DB_POOL;
 use domain::github::{GitHubUser, Issue, IssueComment};
-use domain::rfcbot::{FcpProposal, FcpReviewRequest};
+use domain::rfcbot::{FcpConcern, FcpHold, FcpProposal, FcpReviewRequest, Poll,
+                     PollResponseRequest, ProposalStatus, RfcbotEvent};
 use error::DashResult;
+use github::command::FcpDisposition;
+use teams::SETUP;
 
 #[derive(Serialize)]
 pub struct FcpWithInfo {
@@ -19,7 +25,7 @@ pub fn all_fcps() -> DashResult<Vec<FcpWithInfo>> {
     let conn = &*DB_POOL.get()?;
 
     let proposals = fcp_proposal::table
-        .filter(fcp_proposal::fcp_start.is_null())
+        .filter(fcp_proposal::status.eq(ProposalStatus::Proposed.repr()))
         .load::<FcpProposal>(conn)?;
 
     let mut all_fcps = Vec::new();
@@ -59,6 +65,96 @@ pub fn all_fcps() -> DashResult<Vec<FcpWithInfo>> {
     Ok(all_fcps)
 }
 
+#[derive(Serialize)]
+pub struct RunningFcp {
+    pub fcp: FcpProposal,
+    pub issue: Issue,
+    pub status_comment: IssueComment,
+    /// When the FCP ends, unless it's put on hold.
+    pub fcp_end: NaiveDateTime,
+    pub on_hold: bool,
+}
+
+/// Proposals which are in their final comment period, ending soonest first.
+pub fn running_fcps() -> DashResult<Vec<RunningFcp>> {
+    use domain::schema::{fcp_hold, fcp_proposal, issue, issuecomment};
+
+    let conn = &*DB_POOL.get()?;
+
+    let proposals = fcp_proposal::table
+        .filter(fcp_proposal::status.eq(ProposalStatus::InFcp.repr()))
+        .load::<FcpProposal>(conn)?;
+
+    let mut running = Vec::new();
+
+    for fcp in proposals {
+        let start = match fcp.fcp_start {
+            Some(start) => start,
+            None => continue,
+        };
+
+        let issue = issue::table
+            .filter(issue::id.eq(fcp.fk_issue))
+            .first::<Issue>(conn)?;
+
+        let status_comment = issuecomment::table
+            .filter(issuecomment::id.eq(fcp.fk_bot_tracking_comment))
+            .first::<IssueComment>(conn)?;
+
+        let on_hold = fcp_hold::table
+            .filter(fcp_hold::fk_proposal.eq(fcp.id))
+            .filter(fcp_hold::released_at.is_null())
+            .first::<FcpHold>(conn)
+            .optional()?
+            .is_some();
+
+        let disposition = FcpDisposition::from_str(&fcp.disposition)?;
+        let fcp_end = start + SETUP.get().fcp_duration(&issue.repository, disposition);
+
+        running.push(RunningFcp { fcp, issue, status_comment, fcp_end, on_hold });
+    }
+
+    running.sort_by_key(|r| r.fcp_end);
+
+    Ok(running)
+}
+
+#[derive(Serialize)]
+pub struct FinishedFcp {
+    pub fcp: FcpProposal,
+    pub issue: Issue,
+    pub status_comment: IssueComment,
+}
+
+/// Proposals whose final comment period finished after `since`, most recent first.
+pub fn finished_fcps(since: NaiveDateTime) -> DashResult<Vec<FinishedFcp>> {
+    use domain::schema::{fcp_proposal, issue, issuecomment};
+
+    let conn = &*DB_POOL.get()?;
+
+    let proposals = fcp_proposal::table
+        .filter(fcp_proposal::status.eq(ProposalStatus::Finished.repr()))
+        .filter(fcp_proposal::finished_at.gt(since))
+        .order(fcp_proposal::finished_at.desc())
+        .load::<FcpProposal>(conn)?;
+
+    let mut finished = Vec::new();
+
+    for fcp in proposals {
+        let issue = issue::table
+            .filter(issue::id.eq(fcp.fk_issue))
+            .first::<Issue>(conn)?;
+
+        let status_comment = issuecomment::table
+            .filter(issuecomment::id.eq(fcp.fk_bot_tracking_comment))
+            .first::<IssueComment>(conn)?;
+
+        finished.push(FinishedFcp { fcp, issue, status_comment });
+    }
+
+    Ok(finished)
+}
+
 #[derive(Queryable, Serialize)]
 pub struct IndividualFcp {
     issue: Issue,
@@ -76,7 +172,7 @@ pub fn individual_nags(username: &str) -> DashResult<(GitHubUser, Vec<Individual
 
     let review_requests = fcp_review_request::table
         .inner_join(fcp_proposal::table)
-            .filter(fcp_proposal::fcp_start.is_null())
+            .filter(fcp_proposal::status.eq(ProposalStatus::Proposed.repr()))
         .filter(fcp_review_request::fk_reviewer.eq(user.id))
         .filter(fcp_review_request::reviewed.eq(false))
         .load::<(FcpReviewRequest, FcpProposal)>(conn)?;
@@ -96,3 +192,363 @@ pub fn individual_nags(username: &str) -> DashResult<(GitHubUser, Vec<Individual
 
     Ok((user, fcps))
 }
+
+#[derive(Serialize)]
+pub struct IssueEvent {
+    pub event: RfcbotEvent,
+    pub actor: Option<GitHubUser>,
+}
+
+/// Everything the bot did on an issue, oldest first.
+pub fn issue_history(repository: &str, number: i32) -> DashResult<(Issue, Vec<IssueEvent>)> {
+    use domain::schema::{githubuser, issue, rfcbot_event};
+    let conn = &*DB_POOL.get()?;
+
+    let issue = issue::table
+        .filter(issue::repository.eq(repository))
+        .filter(issue::number.eq(number))
+        .first::<Issue>(conn)?;
+
+    let events = rfcbot_event::table
+        .filter(rfcbot_event::fk_issue.eq(issue.id))
+        .order((rfcbot_event::created_at, rfcbot_event::id))
+        .load::<RfcbotEvent>(conn)?;
+
+    let mut history = Vec::new();
+    for event in events {
+        let actor = match event.fk_actor {
+            Some(actor) => Some(githubuser::table.find(actor).first::<GitHubUser>(conn)?),
+            None => None,
+        };
+
+        history.push(IssueEvent { event, actor });
+    }
+
+    Ok((issue, history))
+}
+
+#[derive(Serialize)]
+pub struct IssueFcp {
+    pub issue: Issue,
+    /// The proposal the issue is going through, or the latest one it went through.
+    pub proposal: Option<ProposalInfo>,
+    pub polls: Vec<PollInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ProposalInfo {
+    pub id: i32,
+    pub status: String,
+    pub disposition: String,
+    pub teams: Vec<String>,
+    pub initiator: String,
+    pub status_comment_url: String,
+    pub proposed_at: NaiveDateTime,
+    pub fcp_start: Option<NaiveDateTime>,
+    /// When the FCP ends, unless it's put on hold.
+    pub fcp_end: Option<NaiveDateTime>,
+    pub on_hold: bool,
+    pub reviews: Vec<(String, bool)>,
+    pub concerns: Vec<ConcernInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ConcernInfo {
+    pub name: String,
+    pub owner: String,
+    pub raised_url: String,
+    pub resolved_url: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PollInfo {
+    pub id: i32,
+    pub question: String,
+    pub teams: Vec<String>,
+    pub initiator: String,
+    pub status_comment_url: String,
+    pub created_at: NaiveDateTime,
+    pub closed: bool,
+    pub responses: Vec<(String, bool)>,
+}
+
+/// The FCP status of a single issue, as shown in its tracking comments.
+pub fn issue_fcp(repository: &str, number: i32) -> DashResult<IssueFcp> {
+    use domain::schema::{fcp_proposal, issue, poll};
+    let conn = &*DB_POOL.get()?;
+
+    let issue = issue::table
+        .filter(issue::repository.eq(repository))
+        .filter(issue::number.eq(number))
+        .first::<Issue>(conn)?;
+
+    let proposal = fcp_proposal::table
+        .filter(fcp_proposal::fk_issue.eq(issue.id))
+        .order((fcp_proposal::proposed_at.desc(), fcp_proposal::id.desc()))
+        .first::<FcpProposal>(conn)
+        .optional()?;
+    let proposal = match proposal {
+        Some(proposal) => Some(proposal_info(&issue, proposal)?),
+        None => None,
+    };
+
+    let mut polls = Vec::new();
+    for survey in poll::table
+        .filter(poll::fk_issue.eq(issue.id))
+        .order(poll::poll_created_at)
+        .load::<Poll>(conn)?
+    {
+        polls.push(poll_info(&issue, survey)?);
+    }
+
+    Ok(IssueFcp { issue, proposal, polls })
+}
+
+fn proposal_info(issue: &Issue, proposal: FcpProposal) -> DashResult<ProposalInfo> {
+    use domain::schema::{fcp_concern, fcp_hold, fcp_review_request, githubuser};
+    let conn = &*DB_POOL.get()?;
+
+    let disposition = FcpDisposition::from_str(&proposal.disposition)?;
+    let fcp_end = proposal.fcp_start
+        .map(|start| start + SETUP.get().fcp_duration(&issue.repository, disposition));
+
+    let on_hold = fcp_hold::table
+        .filter(fcp_hold::fk_proposal.eq(proposal.id))
+        .filter(fcp_hold::released_at.is_null())
+        .first::<FcpHold>(conn)
+        .optional()?
+        .is_some();
+
+    let mut reviews = Vec::new();
+    for review in fcp_review_request::table
+        .filter(fcp_review_request::fk_proposal.eq(proposal.id))
+        .load::<FcpReviewRequest>(conn)?
+    {
+        let reviewer = githubuser::table.find(review.fk_reviewer).first::<GitHubUser>(conn)?;
+        reviews.push((reviewer.login, review.reviewed));
+    }
+    reviews.sort();
+
+    let mut concerns = Vec::new();
+    for concern in fcp_concern::table
+        .filter(fcp_concern::fk_proposal.eq(proposal.id))
+        .order(fcp_concern::name)
+        .load::<FcpConcern>(conn)?
+    {
+        let owner = githubuser::table.find(concern.fk_initiator).first::<GitHubUser>(conn)?;
+
+        concerns.push(ConcernInfo {
+            name: concern.name,
+            owner: owner.login,
+            raised_url: comment_url(issue, concern.fk_initiating_comment),
+            resolved_url: concern.fk_resolved_comment.map(|id| comment_url(issue, id)),
+        });
+    }
+
+    let teams = proposal_teams(&proposal, issue).into_iter().collect();
+
+    let initiator = githubuser::table.find(proposal.fk_initiator).first::<GitHubUser>(conn)?;
+
+    Ok(ProposalInfo {
+        id: proposal.id,
+        status: proposal.status,
+        disposition: proposal.disposition,
+        teams,
+        initiator: initiator.login,
+        status_comment_url: comment_url(issue, proposal.fk_bot_tracking_comment),
+        proposed_at: proposal.proposed_at,
+        fcp_start: proposal.fcp_start,
+        fcp_end,
+        on_hold,
+        reviews,
+        concerns,
+    })
+}
+
+fn poll_info(issue: &Issue, survey: Poll) -> DashResult<PollInfo> {
+    use domain::schema::{githubuser, poll_response_request};
+    let conn = &*DB_POOL.get()?;
+
+    let mut responses = Vec::new();
+    for response in poll_response_request::table
+        .filter(poll_response_request::fk_poll.eq(survey.id))
+        .load::<PollResponseRequest>(conn)?
+    {
+        let respondent = githubuser::table
+            .find(response.fk_respondent)
+            .first::<GitHubUser>(conn)?;
+        responses.push((respondent.login, response.responded));
+    }
+    responses.sort();
+
+    let teams = survey.poll_teams
+        .split(',')
+        .filter(|team| !team.is_empty())
+        .map(String::from)
+        .collect();
+
+    let initiator = githubuser::table.find(survey.fk_initiator).first::<GitHubUser>(conn)?;
+
+    Ok(PollInfo {
+        id: survey.id,
+        question: survey.poll_question,
+        teams,
+        initiator: initiator.login,
+        status_comment_url: comment_url(issue, survey.fk_bot_tracking_comment),
+        created_at: survey.poll_created_at,
+        closed: survey.poll_closed,
+        responses,
+    })
+}
+
+/// The labels of the teams a proposal on `issue` was made to.
+pub fn proposal_teams(proposal: &FcpProposal, issue: &Issue) -> BTreeSet<String> {
+    proposal.teams(&labelled_teams(issue))
+}
+
+fn labelled_teams(issue: &Issue) -> BTreeSet<String> {
+    SETUP.get()
+         .team_labels()
+         .filter(|label| issue.labels.contains(&label.0))
+         .map(|label| label.0.clone())
+         .collect()
+}
+
+fn comment_url(issue: &Issue, comment_id: i32) -> String {
+    format!("https://github.com/{}/issues/{}#issuecomment-{}",
+            issue.repository,
+            issue.number,
+            comment_id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    use domain::rfcbot::EventKind;
+    use github::Harness;
+    use github::command::Label;
+
+    #[test]
+    fn transitions_are_recorded() {
+        let h = Harness::new("rfcbot-test/audit-log");
+        let raiser = &h.members[1];
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        h.comment(raiser, "@rfcbot concern naming");
+        h.comment(raiser, "@rfcbot resolved naming");
+
+        let (issue, history) = issue_history(h.repo, h.issue.number).unwrap();
+        assert_eq!(issue.id, h.issue.id);
+
+        // labels are (re-)applied whenever the status comment is posted,
+        // so only check they're in there
+        let labels_added = history.iter()
+            .filter(|e| e.event.kind == EventKind::LabelAdded.repr())
+            .map(|e| &*e.event.detail)
+            .collect::<BTreeSet<_>>();
+        assert!(labels_added.contains(Label::PFCP.as_str()));
+        assert!(labels_added.contains(Label::DispositionMerge.as_str()));
+
+        let events = history.iter()
+            .filter(|e| !e.event.kind.starts_with("label-"))
+            .map(|e| (&*e.event.kind,
+                      &*e.event.detail,
+                      e.actor.as_ref().map(|a| &*a.login)))
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![
+            ("fcp-proposed", "merge", Some(&*h.members[0].login)),
+            ("concern-raised", "naming", Some(&*raiser.login)),
+            ("concern-resolved", "naming", Some(&*raiser.login)),
+        ]);
+    }
+
+    #[test]
+    fn fcp_status_of_an_issue() {
+        let h = Harness::new("rfcbot-test/fcp-status");
+        let setup = SETUP.get();
+        let team = setup.team_labels().next().unwrap();
+        let raiser = &h.members[1];
+
+        let status = issue_fcp(h.repo, h.issue.number).unwrap();
+        assert!(status.proposal.is_none());
+        assert!(status.polls.is_empty());
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        let concern = h.comment(raiser, "@rfcbot concern naming");
+        h.comment(raiser, "@rfcbot poll Is this a good name?");
+
+        let status = issue_fcp(h.repo, h.issue.number).unwrap();
+        let proposal = status.proposal.unwrap();
+        assert_eq!(proposal.status, "proposed");
+        assert_eq!(proposal.disposition, "merge");
+        assert_eq!(proposal.teams, vec![team.0.clone()]);
+        assert_eq!(proposal.initiator, h.members[0].login);
+        assert!(proposal.fcp_start.is_none() && proposal.fcp_end.is_none());
+        assert_eq!(proposal.reviews.len(), h.members.len());
+        assert!(proposal.reviews.contains(&(h.members[0].login.clone(), true)));
+        assert!(proposal.reviews.contains(&(raiser.login.clone(), false)));
+
+        assert_eq!(proposal.concerns.len(), 1);
+        assert_eq!(proposal.concerns[0].name, "naming");
+        assert_eq!(proposal.concerns[0].owner, raiser.login);
+        assert!(proposal.concerns[0].raised_url.ends_with(&format!("#issuecomment-{}", concern)));
+        assert!(proposal.concerns[0].resolved_url.is_none());
+
+        assert_eq!(status.polls.len(), 1);
+        assert_eq!(status.polls[0].question, "Is this a good name?");
+        assert_eq!(status.polls[0].initiator, raiser.login);
+        assert!(!status.polls[0].closed);
+    }
+
+    #[test]
+    fn dashboard_lists_running_and_finished_fcps() {
+        let h = Harness::new("rfcbot-test/dashboard");
+        let week_ago = Utc::now().naive_utc() - Duration::days(7);
+        let is_ours = |issue: &Issue| issue.id == h.issue.id;
+        let running = || running_fcps().unwrap()
+            .into_iter()
+            .filter(|fcp| is_ours(&fcp.issue))
+            .collect::<Vec<_>>();
+        let finished = || finished_fcps(week_ago).unwrap()
+            .into_iter()
+            .filter(|fcp| is_ours(&fcp.issue))
+            .collect::<Vec<_>>();
+
+        h.comment(&h.members[0], "@rfcbot fcp merge");
+        for member in &h.members[1..] {
+            h.comment(member, "@rfcbot reviewed");
+        }
+
+        let in_fcp = h.proposal().unwrap();
+        let started = in_fcp.fcp_start.unwrap();
+        let duration = SETUP.get().fcp_duration(h.repo, FcpDisposition::Merge);
+        let running_now = running();
+        assert_eq!(running_now.len(), 1);
+        assert_eq!(running_now[0].fcp_end, started + duration);
+        assert!(!running_now[0].on_hold);
+        assert!(finished().is_empty());
+
+        // it's listed under the teams which reviewed it, not those tagged since
+        let setup = SETUP.get();
+        let mut labels = setup.team_labels().map(|label| &*label.0);
+        let (first, second) = (labels.next().unwrap(), labels.next().unwrap());
+        h.relabel(&[first, second]);
+        let fcp = running().remove(0);
+        assert_eq!(proposal_teams(&fcp.fcp, &fcp.issue),
+                   Some(first.to_string()).into_iter().collect());
+
+        h.comment(&h.members[1], "@rfcbot hold waiting for the survey");
+        assert!(running()[0].on_hold);
+        h.comment(&h.members[1], "@rfcbot unhold");
+
+        h.end_fcp();
+
+        assert!(running().is_empty());
+        let finished_now = finished();
+        assert_eq!(finished_now.len(), 1);
+        assert_eq!(finished_now[0].fcp.id, in_fcp.id);
+        assert_eq!(finished_now[0].fcp.disposition, "merge");
+    }
+}
@@ -2,20 +2,19 @@ use std::thread::{spawn, JoinHandle};
 use std::thread;
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 
-use config::{CONFIG, GH_ORGS};
+use config::CONFIG;
 use github;
+use github::client::GitHubApi;
+use teams::SETUP;
 
 pub fn start_scraping() -> JoinHandle<()> {
     // spawn the github scraper in the background
     spawn(|| {
         let sleep_duration = Duration::from_secs(CONFIG.github_interval_mins * 60);
         loop {
-            match github::most_recent_update() {
-                Ok(gh_most_recent) => scrape_github(gh_most_recent),
-                Err(why) => error!("Unable to determine most recent GH update: {:?}", why),
-            }
+            scrape_github();
             info!("GitHub scraper sleeping for {} seconds ({} minutes)",
                   sleep_duration.as_secs(),
                   CONFIG.github_interval_mins);
@@ -24,24 +23,55 @@ pub fn start_scraping() -> JoinHandle<()> {
     })
 }
 
-pub fn scrape_github(since: DateTime<Utc>) {
+pub fn scrape_github() {
+    let setup = SETUP.get();
     let mut repos = Vec::new();
-    for org in &GH_ORGS {
+    for org in setup.watched_orgs() {
         repos.extend(ok_or!(github::GH.org_repos(org), why => {
             error!("Unable to retrieve repos for {}: {:?}", org, why);
             return;
         }));
     }
+    repos.extend(setup.included_repos().map(String::from));
+    repos.sort();
+    repos.dedup();
+    repos.retain(|repo| setup.is_watched(repo));
+
+    // repositories we haven't synced on their own yet start where the last
+    // successful scrape of everything left off
+    let fallback = ok_or!(github::most_recent_update(), why => {
+        error!("Unable to determine most recent GH update: {:?}", why);
+        return;
+    });
 
-    info!("Scraping github activity since {:?}", since);
     let start_time = Utc::now().naive_utc();
+    let mut failed = Vec::new();
     for repo in repos {
-        match github::ingest_since(&repo, since) {
+        let since = ok_or_continue!(github::sync_cursor(&repo, fallback), why => {
+            error!("Unable to determine where to sync {} from: {:?}", repo, why);
+            failed.push(repo.clone());
+        });
+
+        info!("Scraping {} activity since {:?}", repo, since);
+        let repo_start = Utc::now().naive_utc();
+        let result = github::ingest_since(&repo, since);
+        match result {
             Ok(_) => info!("Scraped {} github successfully", repo),
-            Err(why) => error!("Unable to scrape github {}: {:?}", repo, why),
+            Err(ref why) => {
+                error!("Unable to scrape github {}: {:?}", repo, why);
+                failed.push(repo.clone());
+            }
         }
+
+        ok_or!(github::record_sync(&repo, repo_start, &result), why =>
+            error!("Problem recording sync of {}: {:?}", repo, why));
     }
 
-    ok_or!(github::record_successful_update(start_time), why =>
-        error!("Problem recording successful update: {:?}", why));
+    ok_or!(github::record_update(start_time, &failed), why =>
+        error!("Problem recording update: {:?}", why));
+
+    if let Some(limit) = github::GH.rate_limit() {
+        info!("{} of {} GitHub API requests left until {}",
+              limit.remaining, limit.limit, limit.reset);
+    }
 }
@@ -13,8 +13,19 @@ pub fn serve() {
             rocket::ignite()
                 .mount(
                     "/api",
-                    routes![api::all_fcps, api::member_fcps, api::github_webhook],
+                    routes![
+                        api::all_fcps,
+                        api::running_fcps,
+                        api::finished_fcps,
+                        api::member_fcps,
+                        api::issue_fcp,
+                        api::issue_history,
+                        api::rate_limit,
+                        api::stale_repos,
+                        api::github_webhook,
+                    ],
                 )
+                .mount("/admin", routes![admin::replay_deliveries, admin::reload_config])
                 .mount("/", routes![html::all_fcps, html::member_fcps])
                 .launch();
         });
@@ -24,15 +35,18 @@ pub fn serve() {
 }
 
 mod html {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
+    use chrono::{Duration, NaiveDateTime, Utc};
     use rocket::response::content;
+    use serde_json::Value;
     use super::TEMPLATES;
+    use config::CONFIG;
     use error::DashResult;
     use nag;
 
     #[get("/")]
     fn all_fcps() -> DashResult<content::Html<String>> {
-        let mut teams = BTreeMap::new();
+        let mut pending = BTreeMap::new();
         for fcp in nag::all_fcps()? {
             let nag::FcpWithInfo {
                 fcp,
@@ -56,15 +70,61 @@ mod html {
                 "pendingReviewers": pending_reviewers,
             });
 
-            for label in issue.labels.iter().filter(|l| l.starts_with("T-")).cloned() {
-                teams
-                    .entry(label)
-                    .or_insert_with(Vec::new)
-                    .push(record.clone());
-            }
+            add_to_teams(&mut pending, &nag::proposal_teams(&fcp, &issue), record);
+        }
+
+        let now = Utc::now().naive_utc();
+
+        let mut running = BTreeMap::new();
+        for fcp in nag::running_fcps()? {
+            let record = json!({
+                "disposition": fcp.fcp.disposition,
+                "issue": fcp.issue,
+                "statusComment": fcp.status_comment,
+                "countdown": countdown(fcp.fcp_end, fcp.on_hold, now),
+            });
+
+            add_to_teams(&mut running, &nag::proposal_teams(&fcp.fcp, &fcp.issue), record);
+        }
+
+        let since = now - Duration::days(CONFIG.dashboard_finished_days);
+        let mut finished = BTreeMap::new();
+        for fcp in nag::finished_fcps(since)? {
+            let record = json!({
+                "disposition": fcp.fcp.disposition,
+                "issue": fcp.issue,
+                "statusComment": fcp.status_comment,
+                "finishedAt": fcp.fcp.finished_at.map(|at| at.format("%Y-%m-%d").to_string()),
+            });
+
+            add_to_teams(&mut finished, &nag::proposal_teams(&fcp.fcp, &fcp.issue), record);
         }
 
-        let context = teams
+        let context = json!({
+            "model": by_team(pending),
+            "running": by_team(running),
+            "finished": by_team(finished),
+            "finishedDays": CONFIG.dashboard_finished_days,
+        });
+
+        let rendered = TEMPLATES.render("all", &context)?;
+        Ok(content::Html(rendered))
+    }
+
+    /// List the record under each team the proposal was made to.
+    fn add_to_teams(teams: &mut BTreeMap<String, Vec<Value>>,
+                    proposal_teams: &BTreeSet<String>,
+                    record: Value) {
+        for label in proposal_teams.iter().cloned() {
+            teams
+                .entry(label)
+                .or_insert_with(Vec::new)
+                .push(record.clone());
+        }
+    }
+
+    fn by_team(teams: BTreeMap<String, Vec<Value>>) -> Vec<Value> {
+        teams
             .into_iter()
             .map(|(team_label, fcps)| {
                 json!({
@@ -72,10 +132,23 @@ mod html {
                 "fcps": fcps,
             })
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        let rendered = TEMPLATES.render("all", &json!({ "model": context }))?;
-        Ok(content::Html(rendered))
+    /// How long until an FCP ending at `end` is over, in words.
+    fn countdown(end: NaiveDateTime, on_hold: bool, now: NaiveDateTime) -> String {
+        let left = end - now;
+        if on_hold {
+            "on hold".to_string()
+        } else if left.num_days() > 1 {
+            format!("{} days left", left.num_days())
+        } else if left.num_hours() > 1 {
+            format!("{} hours left", left.num_hours())
+        } else if left > Duration::zero() {
+            "ending within the hour".to_string()
+        } else {
+            "ending any moment now".to_string()
+        }
     }
 
     #[get("/fcp/<username>")]
@@ -95,17 +168,33 @@ mod html {
 }
 
 mod api {
+    use chrono::{Duration, Utc};
+    use diesel::pg::PgConnection;
     use rocket_contrib::Json;
     use DB_POOL;
-    use domain::github::GitHubUser;
+    use config::CONFIG;
+    use domain::github::{GitHubSyncCursor, GitHubUser, Issue};
     use error::DashResult;
-    use github::{handle_comment, handle_issue, handle_pr};
-    use github::webhooks::{Event, Payload};
+    use github::{self, GH, handle_comment, handle_deleted_comment, handle_issue, handle_pr};
+    use github::client::{GitHubApi, RateLimit};
+    use github::webhooks::{self, Event, Payload};
     use nag;
+    use teams::SETUP;
 
     #[get("/all")]
     pub fn all_fcps() -> DashResult<Json<Vec<nag::FcpWithInfo>>> { Ok(Json(nag::all_fcps()?)) }
 
+    #[get("/all/in-fcp")]
+    pub fn running_fcps() -> DashResult<Json<Vec<nag::RunningFcp>>> {
+        Ok(Json(nag::running_fcps()?))
+    }
+
+    #[get("/all/finished")]
+    pub fn finished_fcps() -> DashResult<Json<Vec<nag::FinishedFcp>>> {
+        let since = Utc::now().naive_utc() - Duration::days(CONFIG.dashboard_finished_days);
+        Ok(Json(nag::finished_fcps(since)?))
+    }
+
     #[get("/<username>")]
     pub fn member_fcps(
         username: String,
@@ -113,11 +202,55 @@ mod api {
         Ok(Json(nag::individual_nags(&username)?))
     }
 
+    #[get("/fcp/<owner>/<repo>/<number>")]
+    pub fn issue_fcp(owner: String, repo: String, number: i32) -> DashResult<Json<nag::IssueFcp>> {
+        Ok(Json(nag::issue_fcp(&format!("{}/{}", owner, repo), number)?))
+    }
+
+    #[get("/issue/<owner>/<repo>/<number>/history")]
+    pub fn issue_history(
+        owner: String,
+        repo: String,
+        number: i32,
+    ) -> DashResult<Json<(Issue, Vec<nag::IssueEvent>)>> {
+        Ok(Json(nag::issue_history(&format!("{}/{}", owner, repo), number)?))
+    }
+
+    /// The GitHub API budget left, as of the last request we made.
+    #[get("/github/rate-limit")]
+    pub fn rate_limit() -> Json<Option<RateLimit>> { Json(GH.rate_limit()) }
+
+    /// Repositories the scraper is behind on, and why.
+    #[get("/github/stale-repos")]
+    pub fn stale_repos() -> DashResult<Json<Vec<GitHubSyncCursor>>> {
+        let since = Utc::now().naive_utc() - Duration::minutes(CONFIG.github_stale_sync_mins);
+        Ok(Json(github::stale_repos(since)?))
+    }
+
     #[post("/github-webhook", data = "<event>")]
     pub fn github_webhook(event: Event) -> DashResult<()> {
         let conn = &*DB_POOL.get()?;
 
-        match event.payload {
+        // GitHub may deliver the same event more than once
+        if !webhooks::record_delivery(conn, &event)? {
+            info!("Skipping duplicate webhook delivery {}", event.delivery_id);
+            return Ok(());
+        }
+
+        let result = event.parse().and_then(|payload| handle_payload(conn, payload));
+        webhooks::record_outcome(conn, &event.delivery_id, &result)?;
+        result
+    }
+
+    pub fn handle_payload(conn: &PgConnection, payload: Payload) -> DashResult<()> {
+        if let Some(repo) = payload.repository() {
+            if !SETUP.get().is_watched(repo) {
+                info!("Ignoring webhook event from unwatched repository {}", repo);
+                return Ok(());
+            }
+        }
+
+        match payload {
             Payload::Issues(issue_event) => {
                 handle_issue(conn, issue_event.issue, &issue_event.repository.full_name)?;
             }
@@ -130,13 +263,17 @@ mod api {
                 // possible race conditions if we get a comment hook before the issue one (or we
                 // missed the issue one), so make sure the issue exists first
 
-                if comment_event.action != "deleted" {
-                    // TODO handle deleted comments properly
-                    handle_issue(
-                        conn,
-                        comment_event.issue,
-                        &comment_event.repository.full_name,
-                    )?;
+                handle_issue(
+                    conn,
+                    comment_event.issue,
+                    &comment_event.repository.full_name,
+                )?;
+
+                if comment_event.action == "deleted" {
+                    handle_deleted_comment(conn, comment_event.comment)?;
+                } else {
+                    // new and edited comments alike, the latter are diffed
+                    // against what we have stored for any new commands
                     handle_comment(
                         conn,
                         comment_event.comment,
@@ -152,6 +289,81 @@ mod api {
     }
 }
 
+mod admin {
+    use crypto::util::fixed_time_eq;
+    use rocket::Outcome;
+    use rocket::http::Status;
+    use rocket::request::{self, FromRequest, Request};
+    use rocket_contrib::Json;
+    use serde_json::Value;
+
+    use DB_POOL;
+    use config::CONFIG;
+    use error::DashResult;
+    use github::webhooks;
+    use teams::SETUP;
+    use super::api::handle_payload;
+
+    /// Request guard for the admin endpoints, which are only available when
+    /// `ADMIN_TOKEN` is configured and passed as `Authorization: token <ADMIN_TOKEN>`.
+    pub struct Admin;
+
+    impl<'a, 'r> FromRequest<'a, 'r> for Admin {
+        type Error = &'static str;
+
+        fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+            let token = match CONFIG.admin_token {
+                Some(ref token) => format!("token {}", token),
+                None => return Outcome::Failure((Status::NotFound, "admin endpoints are disabled")),
+            };
+
+            match request.headers().get_one("Authorization") {
+                Some(auth) if fixed_time_eq(auth.as_bytes(), token.as_bytes()) =>
+                    Outcome::Success(Admin),
+                _ => {
+                    warn!("Rejected unauthorized admin request: {} {} from {:?}",
+                          request.method(), request.uri(), request.remote());
+                    Outcome::Failure((Status::Unauthorized, "invalid admin token"))
+                }
+            }
+        }
+    }
+
+    /// Process all webhook deliveries which failed, or never finished, once more.
+    #[post("/replay-deliveries")]
+    pub fn replay_deliveries(_admin: Admin) -> DashResult<Json<Vec<Value>>> {
+        let conn = &*DB_POOL.get()?;
+
+        let mut replayed = Vec::new();
+        for delivery in webhooks::failed_deliveries(conn)? {
+            info!("Replaying webhook delivery {} ({})",
+                  delivery.delivery_id,
+                  delivery.event_name);
+
+            let result = webhooks::parse_event(&delivery.event_name, &delivery.body)
+                .and_then(|payload| handle_payload(conn, payload));
+            webhooks::record_outcome(conn, &delivery.delivery_id, &result)?;
+
+            replayed.push(json!({
+                "delivery_id": delivery.delivery_id,
+                "event_name": delivery.event_name,
+                "successful": result.is_ok(),
+                "message": result.err().map(|why| format!("{:?}", why)),
+            }));
+        }
+
+        Ok(Json(replayed))
+    }
+
+    /// Re-read the team configuration, and start using it if it's valid.
+    #[post("/reload-config")]
+    pub fn reload_config(_admin: Admin) -> DashResult<Json<Value>> {
+        let setup = SETUP.reload()?;
+        let teams = setup.team_labels().map(|label| label.0.clone()).collect::<Vec<_>>();
+        Ok(Json(json!({ "teams": teams })))
+    }
+}
+
 lazy_static! {
     static ref TEMPLATES: Handlebars = {
         let mut hbars = Handlebars::new();
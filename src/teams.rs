@@ -1,25 +1,70 @@
-// TODO maybe pull from https://github.com/rust-lang/rust-www/blob/master/_data/team.yml instead
-
 use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 
+use chrono::Duration;
 use diesel::prelude::*;
+use serde_json;
 use toml;
 
 use super::DB_POOL;
+use config::CONFIG;
 use domain::github::GitHubUser;
 use error::*;
+use github::command::FcpDisposition;
 
 //==============================================================================
 // Public API
 //==============================================================================
 
 lazy_static! {
-    pub static ref SETUP: RfcbotConfig = read_rfcbot_cfg_validated();
+    pub static ref SETUP: Setup = {
+        // we want to panic if we're unable to find any of the usernames
+        let cfg = read_rfcbot_cfg_validated()
+            .expect("unable to load the rfcbot configuration.
+if you're running this for tests, make sure you've pulled github users from prod");
+        Setup { current: RwLock::new(Arc::new(cfg)) }
+    };
+}
+
+/// The configuration currently in use, which can be swapped for a new one
+/// at runtime with `reload`.
+pub struct Setup {
+    current: RwLock<Arc<RfcbotConfig>>,
+}
+
+impl Setup {
+    /// Get the current configuration. Hold on to it for as long as a
+    /// consistent view of the configuration is needed, e.g. while processing
+    /// a comment, since it may be replaced at any time.
+    pub fn get(&self) -> Arc<RfcbotConfig> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Re-read and validate the configuration file, and start using it.
+    /// If it's invalid, the current configuration is kept.
+    pub fn reload(&self) -> DashResult<Arc<RfcbotConfig>> {
+        let cfg = Arc::new(read_rfcbot_cfg_validated()?);
+        *self.current.write().unwrap() = cfg.clone();
+        info!("reloaded rfcbot configuration from {}", CONFIG.rfcbot_config_path);
+        Ok(cfg)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RfcbotConfig {
+    /// The organizations and repositories the bot is active in.
+    #[serde(default)]
+    watch: Watch,
     fcp_behaviors: BTreeMap<String, FcpBehavior>,
+    /// Where to read the team roster from, in addition to `teams`.
+    #[serde(default)]
+    team_source: Option<TeamSource>,
+    /// Teams defined here take precedence over those from `team_source`.
+    #[serde(default)]
     teams: BTreeMap<TeamLabel, Team>,
 }
 
@@ -34,6 +79,29 @@ impl RfcbotConfig {
         self.teams.iter()
     }
 
+    /// The organizations whose repositories are scraped, unless excluded.
+    pub fn watched_orgs(&self) -> impl Iterator<Item = &str> {
+        self.watch.orgs.iter().map(|s| s.as_str())
+    }
+
+    /// Repositories which are scraped in addition to those of the watched
+    /// organizations.
+    pub fn included_repos(&self) -> impl Iterator<Item = &str> {
+        self.watch.include_repos.iter().map(|s| s.as_str())
+    }
+
+    /// Is the bot active in this repo, given as `owner/name`?
+    pub fn is_watched(&self, repo: &str) -> bool {
+        let matches = |r: &String| r.eq_ignore_ascii_case(repo);
+        if self.watch.exclude_repos.iter().any(&matches) {
+            return false;
+        }
+
+        let org = repo.split('/').next().unwrap_or_default();
+        self.watch.include_repos.iter().any(&matches) ||
+            self.watch.orgs.iter().any(|o| o.eq_ignore_ascii_case(org))
+    }
+
     /// Are we allowed to auto-close issues after F-FCP in this repo?
     pub fn should_ffcp_auto_close(&self, repo: &str) -> bool {
         self.fcp_behaviors.get(repo).map(|fcp| fcp.close).unwrap_or_default()
@@ -43,14 +111,75 @@ impl RfcbotConfig {
     pub fn should_ffcp_auto_postpone(&self, repo: &str) -> bool {
         self.fcp_behaviors.get(repo).map(|fcp| fcp.postpone).unwrap_or_default()
     }
+
+    /// Should we reply to commands in this repo which we can't parse, or
+    /// which the author isn't allowed to give?
+    pub fn should_give_command_feedback(&self, repo: &str) -> bool {
+        self.fcp_behaviors.get(repo).map(|fcp| !fcp.suppress_command_feedback).unwrap_or(true)
+    }
+
+    /// What happens to the reviews of a proposal in this repo when its
+    /// disposition is changed?
+    pub fn disposition_change_reviews(&self, repo: &str) -> DispositionChangeReviews {
+        self.fcp_behaviors.get(repo)
+            .and_then(|fcp| fcp.disposition_change_reviews)
+            .unwrap_or(DispositionChangeReviews::Reset)
+    }
+
+    /// How long does a final comment period with the given disposition
+    /// last in this repo?
+    pub fn fcp_duration(&self, repo: &str, disposition: FcpDisposition) -> Duration {
+        let days = self.fcp_behaviors.get(repo)
+            .and_then(|fcp| fcp.disposition_durations.get(disposition.repr())
+                               .cloned()
+                               .or(fcp.duration_days))
+            .unwrap_or(DEFAULT_FCP_DURATION_DAYS);
+
+        Duration::days(days)
+    }
+}
+
+/// Where the bot is active. Repositories are given as `owner/name`, and
+/// excluding a repository takes precedence over watching it.
+#[derive(Debug, Default, Deserialize)]
+pub struct Watch {
+    #[serde(default)]
+    orgs: Vec<String>,
+    #[serde(default)]
+    include_repos: Vec<String>,
+    #[serde(default)]
+    exclude_repos: Vec<String>,
 }
 
+/// The length of a final comment period, unless configured otherwise.
+/// This is "one business week" -- 10 days.
+pub const DEFAULT_FCP_DURATION_DAYS: i64 = 10;
+
 #[derive(Debug, Deserialize)]
 pub struct FcpBehavior {
     #[serde(default)]
     close: bool,
     #[serde(default)]
     postpone: bool,
+    #[serde(default)]
+    duration_days: Option<i64>,
+    #[serde(default)]
+    disposition_durations: BTreeMap<String, i64>,
+    #[serde(default)]
+    suppress_command_feedback: bool,
+    #[serde(default)]
+    disposition_change_reviews: Option<DispositionChangeReviews>,
+}
+
+/// What happens to the reviews of a proposal when its disposition is changed.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DispositionChangeReviews {
+    /// Everyone but the member who changed the disposition has to review
+    /// the proposal again.
+    Reset,
+    /// Reviews of the old disposition count for the new one.
+    Keep,
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +187,9 @@ pub struct Team {
     name: String,
     ping: String,
     members: Vec<String>,
+    /// Members who may resolve or transfer concerns raised by others.
+    #[serde(default)]
+    leads: Vec<String>,
 }
 
 impl Team {
@@ -68,37 +200,238 @@ impl Team {
     pub fn member_logins(&self) -> impl Iterator<Item = &str> {
         self.members.iter().map(|s| s.as_str())
     }
+
+    pub fn lead_logins(&self) -> impl Iterator<Item = &str> {
+        self.leads.iter().map(|s| s.as_str())
+    }
+
+    pub fn is_lead(&self, login: &str) -> bool {
+        self.lead_logins().any(|lead| lead == login)
+    }
 }
 
 #[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize)]
 #[serde(transparent)]
 pub struct TeamLabel(pub String);
 
+/// Team data in the format of the rust-lang/team repository.
+#[derive(Debug, Deserialize)]
+pub struct TeamSource {
+    /// Either a checkout of the repository, whose `teams` directory holds
+    /// a TOML file per team, or a JSON or TOML export of the rfcbot data
+    /// generated from it.
+    path: String,
+}
+
 //==============================================================================
 // Implementation details
 //==============================================================================
 
 /// Read the validated `rfcbot.toml` configuration file.
-fn read_rfcbot_cfg_validated() -> RfcbotConfig {
-    let cfg = read_rfcbot_cfg();
+fn read_rfcbot_cfg_validated() -> DashResult<RfcbotConfig> {
+    let cfg = read_rfcbot_cfg()?;
+
+    ok_or!(cfg.watch.validate(), why => {
+        error!("unable to verify the watched repositories: {:?}", why);
+        throw!(why);
+    });
+
+    for (repo, fcp) in &cfg.fcp_behaviors {
+        ok_or!(fcp.validate(), why => {
+            error!("unable to verify the FCP behavior of {}: {:?}", repo, why);
+            throw!(why);
+        });
+    }
 
-    cfg.teams.values().for_each(|team|
-        team.validate()
-            .expect("unable to verify team member from database.
-if you're running this for tests, make sure you've pulled github users from prod")
-    );
+    for team in cfg.teams.values() {
+        ok_or!(team.validate(), why => {
+            error!("unable to verify team {}: {:?}", team.name, why);
+            throw!(why);
+        });
+    }
+
+    Ok(cfg)
+}
 
-    cfg
+/// Read the unprocessed `rfcbot.toml` configuration file, along with the
+/// teams from its team source.
+fn read_rfcbot_cfg() -> DashResult<RfcbotConfig> {
+    let mut cfg = read_rfcbot_cfg_from(&read_file(&CONFIG.rfcbot_config_path)?)?;
+    cfg.load_team_source()?;
+    Ok(cfg)
 }
 
-/// Read the unprocessed `rfcbot.toml` configuration file.
-fn read_rfcbot_cfg() -> RfcbotConfig {
-    read_rfcbot_cfg_from(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rfcbot.toml")))
+fn read_rfcbot_cfg_from(input: &str) -> DashResult<RfcbotConfig> {
+    toml::from_str(input).map_err(|why|
+        DashError::Misc(Some(format!("couldn't parse rfcbot.toml: {}", why))))
 }
 
-fn read_rfcbot_cfg_from(input: &str) -> RfcbotConfig {
-    toml::from_str(input).expect("couldn't parse rfcbot.toml!")
+fn read_file<P: AsRef<Path>>(path: P) -> DashResult<String> {
+    let mut input = String::new();
+    File::open(path)?.read_to_string(&mut input)?;
+    Ok(input)
+}
+
+impl RfcbotConfig {
+    /// Add the teams from the team source, keeping the teams defined in
+    /// `rfcbot.toml` where both have a team with the same label.
+    fn load_team_source(&mut self) -> DashResult<()> {
+        let mut teams = match self.team_source {
+            Some(ref source) => source.read_teams()?,
+            None => return Ok(()),
+        };
+
+        let overrides = mem::replace(&mut self.teams, BTreeMap::new());
+        teams.extend(overrides);
+        self.teams = teams;
+
+        Ok(())
+    }
+}
+
+impl TeamSource {
+    fn read_teams(&self) -> DashResult<BTreeMap<TeamLabel, Team>> {
+        let path = Path::new(&self.path);
+
+        if path.is_dir() {
+            return read_team_repo_checkout(&path.join("teams"));
+        }
+
+        let input = read_file(path)?;
+        let export: TeamExport = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&input)?,
+            Some("toml") => ok_or!(toml::from_str(&input), why =>
+                throw!(DashError::Misc(Some(format!("couldn't parse {}: {}", self.path, why))))),
+            _ => throw!(DashError::Misc(Some(format!("unknown team source format: {}",
+                                                     self.path)))),
+        };
+
+        Ok(export.teams)
+    }
+}
+
+/// The rfcbot data exported from the team repository, which has the same
+/// layout as the `teams` in `rfcbot.toml`.
+#[derive(Debug, Deserialize)]
+struct TeamExport {
+    teams: BTreeMap<TeamLabel, Team>,
+}
+
+/// A `teams/*.toml` file of the team repository.
+#[derive(Debug, Deserialize)]
+struct TeamFile {
+    people: TeamPeople,
+    /// Only teams with an `rfcbot` section take part in FCPs.
+    rfcbot: Option<TeamFileRfcbot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamPeople {
+    #[serde(default)]
+    leads: Vec<String>,
+    members: Vec<TeamFileMember>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TeamFileMember {
+    Login(String),
+    Detailed { github: String },
+}
+
+impl TeamFileMember {
+    fn login(self) -> String {
+        match self {
+            TeamFileMember::Login(login) => login,
+            TeamFileMember::Detailed { github } => github,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamFileRfcbot {
+    label: String,
+    name: String,
+    ping: String,
+    #[serde(default, rename = "exclude-members")]
+    exclude_members: Vec<String>,
+}
+
+/// Read the teams which use rfcbot from the `teams` directory of a checkout
+/// of the team repository.
+fn read_team_repo_checkout(dir: &Path) -> DashResult<BTreeMap<TeamLabel, Team>> {
+    let mut teams = BTreeMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+            continue;
+        }
+
+        let file: TeamFile = ok_or!(toml::from_str(&read_file(&path)?), why =>
+            throw!(DashError::Misc(Some(format!("couldn't parse {}: {}",
+                                                path.display(), why)))));
+
+        let rfcbot = match file.rfcbot {
+            Some(rfcbot) => rfcbot,
+            None => continue,
+        };
+
+        let members = file.people.members
+            .into_iter()
+            .map(TeamFileMember::login)
+            .filter(|login| !rfcbot.exclude_members.contains(login))
+            .collect::<Vec<_>>();
+        let leads = file.people.leads
+            .into_iter()
+            .filter(|login| members.contains(login))
+            .collect();
+
+        teams.insert(TeamLabel(rfcbot.label), Team {
+            name: rfcbot.name,
+            ping: rfcbot.ping,
+            members,
+            leads,
+        });
+    }
+
+    Ok(teams)
+}
+
+impl Watch {
+    fn validate(&self) -> DashResult<()> {
+        // without anything to watch the bot would silently do nothing at all
+        if self.orgs.is_empty() && self.include_repos.is_empty() {
+            throw!(DashError::Misc(Some(
+                "[watch] needs at least one of orgs or include_repos".to_string())));
+        }
+
+        Ok(())
+    }
+}
+
+impl FcpBehavior {
+    fn validate(&self) -> DashResult<()> {
+        // a misspelled disposition would silently get the default duration
+        for (disposition, &days) in &self.disposition_durations {
+            if FcpDisposition::from_str(disposition).is_err() {
+                throw!(DashError::Misc(Some(format!(
+                    "unknown disposition {} in disposition_durations, \
+                     expected merge, close or postpone", disposition))));
+            }
+            if days <= 0 {
+                throw!(DashError::Misc(Some(format!(
+                    "the FCP to {} has to last at least a day", disposition))));
+            }
+        }
+
+        if self.duration_days.map_or(false, |days| days <= 0) {
+            throw!(DashError::Misc(Some(
+                "the FCP has to last at least a day, check duration_days".to_string())));
+        }
+
+        Ok(())
+    }
 }
 
 impl Team {
@@ -106,6 +439,14 @@ impl Team {
         use domain::schema::githubuser::dsl::*;
         let conn = &*(DB_POOL.get()?);
 
+        // leads are picked from the members of the team
+        for lead_login in self.lead_logins() {
+            if !self.member_logins().any(|member| member == lead_login) {
+                throw!(DashError::Misc(Some(format!("{} leads {} but isn't a member of it",
+                                                    lead_login, self.name))));
+            }
+        }
+
         // bail if they don't exist, but we don't want to actually keep the id in ram
         for member_login in self.member_logins() {
             let check_login = githubuser.filter(login.eq(member_login))
@@ -126,22 +467,79 @@ impl Team {
 
 #[cfg(test)]
 pub mod test {
+    use std::sync::{Mutex, MutexGuard};
+
     use super::*;
 
+    lazy_static! {
+        /// Held by tests which change the configuration in use, so that they
+        /// don't undo each other's changes.
+        static ref SETUP_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    fn lock_setup() -> MutexGuard<'static, ()> {
+        SETUP_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A change a test made to the configuration in use, which is undone
+    /// by putting back the previous configuration when it's dropped.
+    #[must_use]
+    pub struct SetupChange {
+        previous: Arc<RfcbotConfig>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl Drop for SetupChange {
+        fn drop(&mut self) {
+            *SETUP.current.write().unwrap() = self.previous.clone();
+        }
+    }
+
+    /// Make `login` a lead of the team labelled `label`, for as long as
+    /// the returned change is held.
+    pub fn add_lead(label: &str, login: &str) -> SetupChange {
+        let lock = lock_setup();
+        let previous = SETUP.get();
+
+        let mut cfg = read_rfcbot_cfg_validated().unwrap();
+        cfg.teams
+           .get_mut(&TeamLabel(label.to_string()))
+           .expect("no such team")
+           .leads
+           .push(login.to_string());
+        *SETUP.current.write().unwrap() = Arc::new(cfg);
+
+        SetupChange { previous, _lock: lock }
+    }
+
     lazy_static! {
         pub static ref TEST_SETUP: RfcbotConfig =
             read_rfcbot_cfg_from(r#"
+[watch]
+orgs = ["rust-lang", "Foobar"]
+include_repos = ["bazquux/gamma"]
+exclude_repos = ["rust-lang/secret"]
+
 [fcp_behaviors]
 
 [fcp_behaviors."rust-lang/alpha"]
 close = true
 postpone = true
+duration_days = 7
+disposition_change_reviews = "keep"
+
+[fcp_behaviors."rust-lang/alpha".disposition_durations]
+close = 3
 
 [fcp_behaviors."foobar/beta"]
 close = false
 
+[fcp_behaviors."foobar/beta".disposition_durations]
+postpone = 14
+
 [fcp_behaviors."bazquux/gamma"]
 postpone = false
+suppress_command_feedback = true
 
 [fcp_behaviors."wibble/epsilon"]
 
@@ -158,6 +556,9 @@ members = [
   "spiderman",
   "captainamerica",
 ]
+leads = [
+  "captainamerica",
+]
 
 [teams.justice-league]
 name = "Justice League of America"
@@ -169,7 +570,7 @@ members = [
   "batman",
   "theflash"
 ]
-"#);
+"#).unwrap();
     }
 
     #[test]
@@ -191,11 +592,15 @@ members = [
             vec!["hulk", "thor", "thevision", "blackwidow",
                  "spiderman", "captainamerica"]);
 
+        assert!(avengers.is_lead("captainamerica"));
+        assert!(!avengers.is_lead("hulk"));
+
         let jsa = map.get("justice-league").unwrap();
         //assert_eq!(jsa.name, "Justice League of America");
         //assert_eq!(jsa.ping, "dc-comics/justice-league");
         assert_eq!(jsa.member_logins().collect::<Vec<_>>(),
             vec!["superman", "wonderwoman", "aquaman", "batman", "theflash"]);
+        assert_eq!(jsa.lead_logins().count(), 0);
 
         // Random non-existent team does not exist:
         assert!(map.get("random").is_none());
@@ -211,18 +616,170 @@ members = [
         assert!(!cfg.should_ffcp_auto_postpone("wibble/epsilon"));
         assert!(!cfg.should_ffcp_auto_close("random"));
         assert!(!cfg.should_ffcp_auto_postpone("random"));
+
+        // Watched repos correct:
+        assert_eq!(cfg.watched_orgs().collect::<Vec<_>>(), vec!["rust-lang", "Foobar"]);
+        assert_eq!(cfg.included_repos().collect::<Vec<_>>(), vec!["bazquux/gamma"]);
+        assert!(cfg.is_watched("rust-lang/alpha"));
+        assert!(cfg.is_watched("foobar/beta"));
+        assert!(cfg.is_watched("bazquux/gamma"));
+        assert!(!cfg.is_watched("bazquux/delta"));
+        assert!(!cfg.is_watched("rust-lang/secret"));
+        assert!(!cfg.is_watched("random"));
+
+        // Command feedback correct:
+        assert!(cfg.should_give_command_feedback("rust-lang/alpha"));
+        assert!(!cfg.should_give_command_feedback("bazquux/gamma"));
+        assert!(cfg.should_give_command_feedback("random"));
+
+        // Disposition changes correct:
+        assert_eq!(cfg.disposition_change_reviews("rust-lang/alpha"),
+                   DispositionChangeReviews::Keep);
+        assert_eq!(cfg.disposition_change_reviews("foobar/beta"),
+                   DispositionChangeReviews::Reset);
+        assert_eq!(cfg.disposition_change_reviews("random"),
+                   DispositionChangeReviews::Reset);
+
+        // FCP durations correct:
+        let default = Duration::days(DEFAULT_FCP_DURATION_DAYS);
+        assert_eq!(cfg.fcp_duration("rust-lang/alpha", FcpDisposition::Merge),
+                   Duration::days(7));
+        assert_eq!(cfg.fcp_duration("rust-lang/alpha", FcpDisposition::Close),
+                   Duration::days(3));
+        assert_eq!(cfg.fcp_duration("rust-lang/alpha", FcpDisposition::Postpone),
+                   Duration::days(7));
+        assert_eq!(cfg.fcp_duration("foobar/beta", FcpDisposition::Merge), default);
+        assert_eq!(cfg.fcp_duration("foobar/beta", FcpDisposition::Postpone),
+                   Duration::days(14));
+        assert_eq!(cfg.fcp_duration("wibble/epsilon", FcpDisposition::Close), default);
+        assert_eq!(cfg.fcp_duration("random", FcpDisposition::Merge), default);
     }
 
     #[test]
     fn cfg_file_wellformed() {
         // Just parse it and ensure that we get no panics for now!
         // This is a crap test; but, better than nothing.
-        let _ = read_rfcbot_cfg();
+        read_rfcbot_cfg().unwrap();
+    }
+
+    #[test]
+    fn invalid_cfg_is_rejected() {
+        // a lead who isn't on the team
+        assert!(read_rfcbot_cfg_from(r#"
+[fcp_behaviors]
+
+[teams.T-avengers]
+name = "The Avengers"
+ping = "marvel/avengers"
+members = ["hulk"]
+leads = ["thanos"]
+"#).unwrap().teams.values().all(|team| team.validate().is_err()));
+
+        assert!(read_rfcbot_cfg_from("[teams.T-avengers]\nname = 42").is_err());
+
+        // misspelled dispositions and FCPs which don't last at all
+        let fcp_behavior = |behavior: &str| {
+            let cfg = read_rfcbot_cfg_from(&format!("[fcp_behaviors.\"rust-lang/alpha\"]\n{}",
+                                                    behavior)).unwrap();
+            cfg.fcp_behaviors["rust-lang/alpha"].validate()
+        };
+        assert!(fcp_behavior("disposition_durations = { merg = 7 }").is_err());
+        assert!(fcp_behavior("disposition_durations = { close = 0 }").is_err());
+        assert!(fcp_behavior("duration_days = -1").is_err());
+        assert!(fcp_behavior("duration_days = 3\ndisposition_durations = { merge = 7 }").is_ok());
+        assert!(TEST_SETUP.fcp_behaviors.values().all(|fcp| fcp.validate().is_ok()));
+
+        // nothing to watch
+        assert!(read_rfcbot_cfg_from("[fcp_behaviors]").unwrap().watch.validate().is_err());
+        assert!(read_rfcbot_cfg_from("[watch]\norgs = []\n[fcp_behaviors]")
+            .unwrap().watch.validate().is_err());
+        assert!(TEST_SETUP.watch.validate().is_ok());
+    }
+
+    fn fixture(name: &str) -> String {
+        format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn members(teams: &BTreeMap<TeamLabel, Team>, label: &str) -> Vec<String> {
+        teams[&TeamLabel(label.to_string())].members.clone()
+    }
+
+    #[test]
+    fn team_repo_checkout() {
+        let source = TeamSource { path: fixture("team-repo") };
+        let teams = source.read_teams().unwrap();
+
+        // the release team doesn't use rfcbot
+        assert_eq!(teams.keys().map(|tl| tl.0.clone()).collect::<Vec<_>>(),
+                   vec!["T-lang", "T-libs"]);
+
+        let lang = &teams[&TeamLabel("T-lang".to_string())];
+        assert_eq!(lang.name, "Language");
+        assert_eq!(lang.ping(), "rust-lang/lang");
+        // excluded members are dropped, detailed members are supported
+        assert_eq!(lang.member_logins().collect::<Vec<_>>(),
+                   vec!["nikomatsakis", "joshtriplett", "cramertj", "withoutboats"]);
+        assert_eq!(lang.lead_logins().collect::<Vec<_>>(),
+                   vec!["nikomatsakis", "joshtriplett"]);
+
+        assert_eq!(members(&teams, "T-libs"), vec!["alexcrichton", "dtolnay", "sfackler"]);
+    }
+
+    #[test]
+    fn team_repo_export() {
+        let checkout = TeamSource { path: fixture("team-repo") }.read_teams().unwrap();
+        let export = TeamSource { path: fixture("team-export.json") }.read_teams().unwrap();
+
+        assert_eq!(checkout.keys().collect::<Vec<_>>(), export.keys().collect::<Vec<_>>());
+        for label in checkout.keys() {
+            assert_eq!(checkout[label].members, export[label].members);
+            assert_eq!(checkout[label].ping, export[label].ping);
+        }
+
+        let readme = format!("{}/README.md", env!("CARGO_MANIFEST_DIR"));
+        assert!(TeamSource { path: readme }.read_teams().is_err());
+    }
+
+    #[test]
+    fn team_source_merged_with_local_teams() {
+        let mut cfg = read_rfcbot_cfg_from(&format!(r#"
+[fcp_behaviors]
+
+[team_source]
+path = "{}"
+
+[teams.T-libs]
+name = "Libraries"
+ping = "rust-lang/libs"
+members = ["alexcrichton", "SimonSapin"]
+
+[teams.T-core]
+name = "Core"
+ping = "rust-lang/core"
+members = ["aturon"]
+"#, fixture("team-repo"))).unwrap();
+        cfg.load_team_source().unwrap();
+
+        assert_eq!(cfg.team_labels().map(|tl| tl.0.clone()).collect::<Vec<_>>(),
+                   vec!["T-core", "T-lang", "T-libs"]);
+        // local definitions win
+        assert_eq!(members(&cfg.teams, "T-libs"), vec!["alexcrichton", "SimonSapin"]);
+        assert_eq!(members(&cfg.teams, "T-core"), vec!["aturon"]);
+        assert_eq!(members(&cfg.teams, "T-lang").len(), 4);
+    }
+
+    #[test]
+    fn reload_swaps_in_cfg() {
+        let _setup = lock_setup();
+        let before = SETUP.get();
+        let after = SETUP.reload().unwrap();
+        assert_eq!(before.team_labels().collect::<Vec<_>>(),
+                   after.team_labels().collect::<Vec<_>>());
     }
 
     #[test]
     fn team_members_exist() {
-        for (label, _) in SETUP.teams.iter() {
+        for (label, _) in SETUP.get().teams.iter() {
             println!("found team {:?}", label);
         }
     }